@@ -59,4 +59,10 @@ pub fn cli() -> Command {
                 .action(ArgAction::SetTrue)
                 .global(true),
         )
+        .subcommand(
+            Command::new("build").about("Builds the site into the output directory").arg(
+                opt("base-path", "Directory the site is served from, overrides `build.base_path`")
+                    .value_name("PATH"),
+            ),
+        )
 }
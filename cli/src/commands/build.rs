@@ -1,3 +1,4 @@
+use clap::ArgMatches;
 use glob::glob;
 use marston_core::{
     MPath, MResult,
@@ -7,7 +8,11 @@ use marston_core::{
 use rayon::prelude::*;
 use std::sync::{Arc, Mutex};
 
-pub fn build_command(ctx: Context) -> MResult<()> {
+pub fn build_command(mut ctx: Context, args: &ArgMatches) -> MResult<()> {
+    if let Some(base_path) = args.get_one::<String>("base-path") {
+        ctx.set_base_path(base_path)?;
+    }
+
     if ctx.build_dir().exists() {
         clear_dir(ctx.build_dir())?;
     }
@@ -23,10 +28,17 @@ pub fn build_command(ctx: Context) -> MResult<()> {
     let ctx = Arc::new(Mutex::new(ctx));
     files.par_iter().try_for_each(|file| {
         let mut ctx = ctx.lock().unwrap();
+        if file.is_dir() {
+            return Ok(());
+        }
+
         if file.extension() != Some("mr") {
             let stripped = file.strip_prefix(ctx.main_dir())?;
             let out = ctx.build_dir().join(stripped);
 
+            if let Some(parent) = out.parent() {
+                fs_err::create_dir_all(parent)?;
+            }
             fs_err::copy(file, &out)?;
             return Ok(());
         }
@@ -34,5 +46,5 @@ pub fn build_command(ctx: Context) -> MResult<()> {
         ctx.process_file(file)
     })?;
 
-    Ok(())
+    ctx.lock().unwrap().finish()
 }
@@ -1,6 +1,7 @@
 use crate::{
     clap::cli, commands::build::build_command, logger::init_logger, panic::setup_panic_handler,
 };
+use ::clap::ArgMatches;
 use anyhow::bail;
 use log::{error, info};
 use marston_core::{MResult, context::Context, fs::to_mpath};
@@ -19,7 +20,7 @@ fn main() -> MResult<()> {
     init_logger()?;
     setup_panic_handler(args.get_flag("no-backtrace"));
 
-    let (cmd, _) = match args.subcommand() {
+    let (cmd, cmd_args) = match args.subcommand() {
         Some((cmd, args)) => (cmd, args),
         None => {
             cli().print_help()?;
@@ -31,18 +32,18 @@ fn main() -> MResult<()> {
     let context = Context::new(&to_mpath(c_dir)?)?;
     info!("current project: {}", context.name());
 
-    if let Err(err) = execute(context, cmd) {
+    if let Err(err) = execute(context, cmd, cmd_args) {
         error!("{err}");
     }
 
     Ok(())
 }
 
-pub fn execute(ctx: Context, name: &str) -> MResult<()> {
+pub fn execute(ctx: Context, name: &str, args: &ArgMatches) -> MResult<()> {
     let cmd = match name {
         "build" => build_command,
         _ => bail!("Unknown command: {name}"),
     };
 
-    cmd(ctx)
+    cmd(ctx, args)
 }
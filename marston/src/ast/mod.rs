@@ -2,6 +2,7 @@ use crate::{
     Span,
     ast::ident_table::{get_or_intern, resolve},
 };
+use itertools::Either;
 use lasso::{Key, Spur};
 use rustc_hash::FxHashMap;
 use std::{
@@ -12,8 +13,9 @@ use std::{
 
 pub mod ident_table;
 pub mod parser;
+pub mod selector;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct MarstonDocument {
     pub blocks: Vec<Block>,
 }
@@ -27,7 +29,7 @@ impl MarstonDocument {
         let mut current_blocks: Vec<&Block> = Vec::new();
 
         let first_name = names[0];
-        for block in &self.blocks {
+        for block in self.root_blocks() {
             if let Some(ref interned) = block.name {
                 if interned.key == first_name {
                     current_blocks.push(block);
@@ -97,10 +99,31 @@ impl Block {
         results
     }
 
+    /// # Panics
+    ///
+    /// If the block is anonymous.
     pub fn name(&self) -> Interned {
         self.name.as_ref().unwrap().clone()
     }
 
+    /// Whether this is an `.html` block, which stands for the `<html>` element itself.
+    pub fn is_root_element(&self) -> bool {
+        self.name.as_ref().is_some_and(|name| resolve(name.key) == "html")
+    }
+
+    /// Whether this is a `.page` block, which holds build metadata and is never rendered.
+    pub fn is_front_matter(&self) -> bool {
+        self.name.as_ref().is_some_and(|name| resolve(name.key) == "page")
+    }
+
+    /// Direct child blocks, skipping text.
+    pub fn child_blocks(&self) -> impl Iterator<Item = &Block> {
+        self.children.iter().filter_map(|child| match child {
+            Node::Block(block) => Some(block),
+            Node::Text(_) => None,
+        })
+    }
+
     pub fn find_all_child_blocks(&self, name: Spur) -> Vec<&Block> {
         let mut results = Vec::new();
 
@@ -201,7 +224,28 @@ impl MarstonDocument {
     }
 
     pub fn find_block_by_name(&self, name: Spur) -> Option<&Block> {
-        self.blocks.iter().find(|e| e.name.as_ref().map(|n| n.key) == Some(name))
+        self.root_blocks().find(|e| e.name.as_ref().map(|n| n.key) == Some(name))
+    }
+
+    /// The optional top-level `.html` block, whose attributes are set on the `<html>` element.
+    pub fn html_block(&self) -> Option<&Block> {
+        self.blocks.iter().find(|block| block.is_root_element())
+    }
+
+    /// The optional top-level `.page` block with the build metadata of the document.
+    pub fn front_matter(&self) -> Option<&Block> {
+        self.blocks.iter().find(|block| block.is_front_matter())
+    }
+
+    /// Top-level blocks, looking through the `.html` block if head and body are wrapped in it.
+    pub fn root_blocks(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.iter().flat_map(|block| {
+            if block.is_root_element() {
+                Either::Left(block.child_blocks())
+            } else {
+                Either::Right(std::iter::once(block))
+            }
+        })
     }
 }
 
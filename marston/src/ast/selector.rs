@@ -0,0 +1,608 @@
+//! CSS-like selectors over a [`MarstonDocument`].
+//!
+//! Supported syntax:
+//! - element names and `*`
+//! - descendant (`body a`) and child (`head > link`) combinators
+//! - attribute selectors: `[attr]`, `[attr=value]`, `[attr~=value]`, `[attr^=value]`,
+//!   `[attr$=value]` and `[attr*=value]`, where the value may be quoted
+//! - `#id` and `.class` as shorthands for the `id` and `class` attributes
+//! - `:first-child` and `:not(...)`
+//! - selector lists separated by `,`
+//!
+//! Attribute names are written the way they are in Marston sources, e.g. `[aria_label]`.
+
+use crate::{
+    MResult,
+    ast::{Block, MarstonDocument, ident_table::get_or_intern},
+};
+use anyhow::anyhow;
+use lasso::Spur;
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug, Clone)]
+pub struct Selector {
+    source: String,
+    alternatives: Vec<ComplexSelector>,
+}
+
+#[derive(Debug, Clone)]
+struct ComplexSelector {
+    /// Only matches if the first compound is a top-level block, or a child of the `.html` block
+    rooted: bool,
+    compounds: Vec<Compound>,
+    /// `combinators[i]` sits between `compounds[i]` and `compounds[i + 1]`
+    combinators: Vec<Combinator>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Compound {
+    name: Option<Spur>,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone)]
+enum Filter {
+    Attribute { name: String, matcher: Option<(AttributeOperator, String)> },
+    FirstChild,
+    Not(Vec<Compound>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttributeOperator {
+    /// `=`
+    Equals,
+    /// `~=`, whitespace separated list contains the value
+    Includes,
+    /// `^=`
+    Prefix,
+    /// `$=`
+    Suffix,
+    /// `*=`
+    Substring,
+}
+
+/// A block together with what the selector needs to know about where it is.
+#[derive(Clone, Copy)]
+pub struct Position<'a> {
+    pub block: &'a Block,
+    /// Whether this is the first block among its siblings
+    pub first_child: bool,
+}
+
+impl Selector {
+    pub fn parse(source: &str) -> MResult<Self> {
+        let mut parser = SelectorParser { source, pos: 0 };
+        let alternatives = parser.parse_list()?;
+
+        Ok(Self { source: source.trim().to_string(), alternatives })
+    }
+
+    /// Exact chain of block names starting at the top level of the document,
+    /// e.g. `["head", "link"]` matches `link` blocks directly inside the top-level `head`.
+    pub fn from_path(names: &[&str]) -> Self {
+        let compounds = names
+            .iter()
+            .map(|name| Compound { name: Some(get_or_intern(name)), filters: vec![] })
+            .collect::<Vec<_>>();
+        let combinators = vec![Combinator::Child; compounds.len().saturating_sub(1)];
+
+        Self {
+            source: names.join(" > "),
+            alternatives: vec![ComplexSelector { rooted: true, compounds, combinators }],
+        }
+    }
+
+    /// All matching blocks in document order.
+    pub fn select<'a>(&self, doc: &'a MarstonDocument) -> Vec<&'a Block> {
+        let mut results = vec![];
+        let mut path = vec![];
+        self.collect_matches(doc.blocks.iter(), &mut path, &mut results);
+        results
+    }
+
+    /// Tests the last block of `path`, which holds every block from the top level down to it.
+    pub fn matches_path(&self, path: &[Position]) -> bool {
+        self.alternatives.iter().any(|alternative| alternative.matches(path))
+    }
+
+    /// Names of the blocks this selector can match, or `None` if it can match any block.
+    pub fn subject_names(&self) -> Option<Vec<Spur>> {
+        self.alternatives.iter().map(|alternative| alternative.compounds.last()?.name).collect()
+    }
+
+    fn collect_matches<'a>(
+        &self,
+        blocks: impl Iterator<Item = &'a Block>,
+        path: &mut Vec<Position<'a>>,
+        results: &mut Vec<&'a Block>,
+    ) {
+        for (index, block) in blocks.enumerate() {
+            if block.name.is_none() {
+                continue;
+            }
+
+            path.push(Position { block, first_child: index == 0 });
+
+            if self.matches_path(path) {
+                results.push(block);
+            }
+            self.collect_matches(block.child_blocks(), path, results);
+
+            path.pop();
+        }
+    }
+}
+
+impl ComplexSelector {
+    /// `path` ends with the block being tested and starts with its top-level ancestor.
+    fn matches(&self, path: &[Position]) -> bool {
+        self.matches_from(self.compounds.len() - 1, path)
+    }
+
+    fn matches_from(&self, index: usize, path: &[Position]) -> bool {
+        let Some((current, ancestors)) = path.split_last() else {
+            return false;
+        };
+
+        if !self.compounds[index].matches(*current) {
+            return false;
+        }
+
+        if index == 0 {
+            return !self.rooted
+                || match ancestors {
+                    [] => true,
+                    [parent] => parent.block.is_root_element(),
+                    _ => false,
+                };
+        }
+
+        match self.combinators[index - 1] {
+            Combinator::Child => self.matches_from(index - 1, ancestors),
+            Combinator::Descendant => (1..=ancestors.len())
+                .rev()
+                .any(|len| self.matches_from(index - 1, &ancestors[..len])),
+        }
+    }
+}
+
+impl Compound {
+    fn matches(&self, position: Position) -> bool {
+        let Some(name) = &position.block.name else {
+            return false;
+        };
+
+        if self.name.is_some_and(|expected| expected != name.key) {
+            return false;
+        }
+
+        self.filters.iter().all(|filter| filter.matches(position))
+    }
+}
+
+impl Filter {
+    fn matches(&self, position: Position) -> bool {
+        match self {
+            Filter::Attribute { name, matcher } => {
+                let Some(attr) = position.block.get_attribute(name) else {
+                    return false;
+                };
+                let Some((operator, expected)) = matcher else {
+                    return true;
+                };
+
+                let value = attr.value.kind.to_string();
+                let value = value.trim();
+
+                match operator {
+                    AttributeOperator::Equals => value == expected,
+                    AttributeOperator::Includes => value.split_whitespace().any(|v| v == expected),
+                    AttributeOperator::Prefix => {
+                        !expected.is_empty() && value.starts_with(expected.as_str())
+                    }
+                    AttributeOperator::Suffix => {
+                        !expected.is_empty() && value.ends_with(expected.as_str())
+                    }
+                    AttributeOperator::Substring => {
+                        !expected.is_empty() && value.contains(expected.as_str())
+                    }
+                }
+            }
+            Filter::FirstChild => position.first_child,
+            Filter::Not(compounds) => !compounds.iter().any(|compound| compound.matches(position)),
+        }
+    }
+}
+
+impl Display for Selector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl From<Vec<&str>> for Selector {
+    fn from(names: Vec<&str>) -> Self {
+        Self::from_path(&names)
+    }
+}
+
+/// For selectors written in the source code, an invalid one is a bug and panics. Selectors built
+/// at runtime go through [`Selector::parse`].
+impl From<&'static str> for Selector {
+    fn from(source: &'static str) -> Self {
+        Self::parse(source).unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+struct SelectorParser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl<'a> SelectorParser<'a> {
+    fn parse_list(&mut self) -> MResult<Vec<ComplexSelector>> {
+        let mut alternatives = vec![self.parse_complex()?];
+
+        while self.eat(',') {
+            alternatives.push(self.parse_complex()?);
+        }
+
+        self.skip_whitespace();
+        if let Some(c) = self.peek() {
+            return Err(self.error(&format!("unexpected '{c}'")));
+        }
+
+        Ok(alternatives)
+    }
+
+    fn parse_complex(&mut self) -> MResult<ComplexSelector> {
+        self.skip_whitespace();
+        let mut compounds = vec![self.parse_compound()?];
+        let mut combinators = vec![];
+
+        loop {
+            let had_whitespace = self.skip_whitespace();
+
+            let combinator = match self.peek() {
+                Some('>') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    Combinator::Child
+                }
+                Some(',' | ')') | None => break,
+                Some(_) if had_whitespace => Combinator::Descendant,
+                Some(c) => return Err(self.error(&format!("unexpected '{c}'"))),
+            };
+
+            combinators.push(combinator);
+            compounds.push(self.parse_compound()?);
+        }
+
+        Ok(ComplexSelector { rooted: false, compounds, combinators })
+    }
+
+    fn parse_compound(&mut self) -> MResult<Compound> {
+        let mut compound = Compound::default();
+        let start = self.pos;
+
+        if !self.eat('*') && self.peek().is_some_and(is_name_char) {
+            compound.name = Some(get_or_intern(self.parse_name()?));
+        }
+
+        loop {
+            match self.peek() {
+                Some('[') => {
+                    self.pos += 1;
+                    compound.filters.push(self.parse_attribute()?);
+                }
+                Some('#') => {
+                    self.pos += 1;
+                    let id = self.parse_name()?.to_string();
+                    compound.filters.push(Filter::Attribute {
+                        name: "id".to_string(),
+                        matcher: Some((AttributeOperator::Equals, id)),
+                    });
+                }
+                Some('.') => {
+                    self.pos += 1;
+                    let class = self.parse_name()?.to_string();
+                    compound.filters.push(Filter::Attribute {
+                        name: "class".to_string(),
+                        matcher: Some((AttributeOperator::Includes, class)),
+                    });
+                }
+                Some(':') => {
+                    self.pos += 1;
+                    compound.filters.push(self.parse_pseudo_class()?);
+                }
+                _ => break,
+            }
+        }
+
+        if self.pos == start {
+            return Err(self.error("expected a selector"));
+        }
+
+        Ok(compound)
+    }
+
+    fn parse_attribute(&mut self) -> MResult<Filter> {
+        self.skip_whitespace();
+        let name = self.parse_name()?.to_string();
+        self.skip_whitespace();
+
+        if self.eat(']') {
+            return Ok(Filter::Attribute { name, matcher: None });
+        }
+
+        let operator = match self.peek() {
+            Some('=') => AttributeOperator::Equals,
+            Some('~') => AttributeOperator::Includes,
+            Some('^') => AttributeOperator::Prefix,
+            Some('$') => AttributeOperator::Suffix,
+            Some('*') => AttributeOperator::Substring,
+            _ => return Err(self.error("expected ']' or an attribute operator")),
+        };
+        self.pos += 1;
+        if operator != AttributeOperator::Equals && !self.eat('=') {
+            return Err(self.error("expected '='"));
+        }
+
+        self.skip_whitespace();
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+
+        if !self.eat(']') {
+            return Err(self.error("expected ']'"));
+        }
+
+        Ok(Filter::Attribute { name, matcher: Some((operator, value)) })
+    }
+
+    fn parse_value(&mut self) -> MResult<String> {
+        let rest = &self.source[self.pos..];
+
+        if let Some(quote @ ('"' | '\'')) = self.peek() {
+            let end = rest[1..].find(quote).ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += end + 2;
+            return Ok(rest[1..=end].to_string());
+        }
+
+        let end = rest.find(|c: char| c == ']' || c.is_whitespace()).unwrap_or(rest.len());
+        if end == 0 {
+            return Err(self.error("expected an attribute value"));
+        }
+        self.pos += end;
+        Ok(rest[..end].to_string())
+    }
+
+    fn parse_pseudo_class(&mut self) -> MResult<Filter> {
+        let name = self.parse_name()?;
+
+        match name {
+            "first-child" => Ok(Filter::FirstChild),
+            "not" => {
+                if !self.eat('(') {
+                    return Err(self.error("expected '(' after ':not'"));
+                }
+
+                let mut compounds = vec![];
+                loop {
+                    self.skip_whitespace();
+                    compounds.push(self.parse_compound()?);
+                    self.skip_whitespace();
+                    if !self.eat(',') {
+                        break;
+                    }
+                }
+
+                if !self.eat(')') {
+                    return Err(self.error("expected ')'"));
+                }
+
+                Ok(Filter::Not(compounds))
+            }
+            _ => Err(self.error(&format!("unsupported pseudo-class ':{name}'"))),
+        }
+    }
+
+    fn parse_name(&mut self) -> MResult<&'a str> {
+        let source = self.source;
+        let rest = &source[self.pos..];
+        let end = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+
+        if end == 0 {
+            return Err(self.error("expected a name"));
+        }
+
+        self.pos += end;
+        Ok(&rest[..end])
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns whether any whitespace was skipped.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+        self.pos != start
+    }
+
+    fn error(&self, message: &str) -> anyhow::Error {
+        anyhow!("invalid selector '{}': {message} at position {}", self.source, self.pos)
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{Attribute, Interned, Node, Value, ValueKind};
+
+    fn block(id: usize, name: &str, attributes: &[(&str, &str)], children: Vec<Block>) -> Block {
+        let mut block = Block::new(Some(Interned::new(get_or_intern(name), 0..0)), id);
+        block.attributes = attributes
+            .iter()
+            .map(|(key, value)| {
+                Attribute::new(
+                    Interned::new(get_or_intern(key), 0..0),
+                    Value { kind: ValueKind::String((*value).to_string()), span: 0..0 },
+                )
+            })
+            .collect();
+        block.children = children.into_iter().map(Node::Block).collect();
+        block
+    }
+
+    /// ```text
+    /// head(1) { link(2)[rel=stylesheet] meta(3)[name=robots] }
+    /// body(4) {
+    ///     a(5)[href=/about class="nav main"]
+    ///     main(6) { p(7) { a(8)[href=https://example.com] } a(9)[id=top] }
+    /// }
+    /// ```
+    fn doc() -> MarstonDocument {
+        MarstonDocument {
+            blocks: vec![
+                block(
+                    1,
+                    "head",
+                    &[],
+                    vec![
+                        block(2, "link", &[("rel", "stylesheet")], vec![]),
+                        block(3, "meta", &[("name", "robots")], vec![]),
+                    ],
+                ),
+                block(
+                    4,
+                    "body",
+                    &[],
+                    vec![
+                        block(5, "a", &[("href", "/about"), ("class", "nav main")], vec![]),
+                        block(
+                            6,
+                            "main",
+                            &[],
+                            vec![
+                                block(
+                                    7,
+                                    "p",
+                                    &[],
+                                    vec![block(8, "a", &[("href", "https://example.com")], vec![])],
+                                ),
+                                block(9, "a", &[("id", "top")], vec![]),
+                            ],
+                        ),
+                    ],
+                ),
+            ],
+        }
+    }
+
+    fn select(selector: &str) -> Vec<usize> {
+        Selector::parse(selector).unwrap().select(&doc()).iter().map(|block| block.id).collect()
+    }
+
+    #[test]
+    fn matches_names_and_universal() {
+        assert_eq!(select("a"), [5, 8, 9]);
+        assert_eq!(select("head *"), [2, 3]);
+        assert_eq!(select("video"), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn combinators() {
+        assert_eq!(select("body a"), [5, 8, 9]);
+        assert_eq!(select("body > a"), [5]);
+        assert_eq!(select("main > a"), [9]);
+        assert_eq!(select("body>main   p > a"), [8]);
+        assert_eq!(select("head > a"), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn attribute_operators() {
+        assert_eq!(select("[href]"), [5, 8]);
+        assert_eq!(select("link[rel=stylesheet]"), [2]);
+        assert_eq!(select("meta[name='robots']"), [3]);
+        assert_eq!(select("a[class~=nav]"), [5]);
+        assert_eq!(select("a[class~=na]"), [] as [usize; 0]);
+        assert_eq!(select("a[href^=https]"), [8]);
+        assert_eq!(select("a[href$=\"/about\"]"), [5]);
+        assert_eq!(select("a[href*=example]"), [8]);
+        assert_eq!(select("a[href^='']"), [] as [usize; 0]);
+        assert_eq!(select("a#top"), [9]);
+        assert_eq!(select(".main"), [5]);
+    }
+
+    #[test]
+    fn pseudo_classes_and_lists() {
+        assert_eq!(select("body a:first-child"), [5, 8]);
+        assert_eq!(select("body :not(a, main)"), [7]);
+        assert_eq!(select("a:not([href])"), [9]);
+        assert_eq!(select("link, main > a"), [2, 9]);
+    }
+
+    #[test]
+    fn paths_are_rooted() {
+        let doc = doc();
+        let ids = |selector: Selector| {
+            selector.select(&doc).iter().map(|block| block.id).collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(Selector::from(vec!["body", "a"])), [5]);
+        assert_eq!(ids(Selector::from(vec!["main", "a"])), [] as [usize; 0]);
+        assert_eq!(Selector::from_path(&["head", "link"]).to_string(), "head > link");
+    }
+
+    #[test]
+    fn subject_names() {
+        let names = |selector: &str| {
+            Selector::parse(selector).unwrap().subject_names().map(|names| {
+                names.into_iter().map(crate::ast::ident_table::resolve).collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(names("head > link, body a"), Some(vec!["link".to_string(), "a".to_string()]));
+        assert_eq!(names("body [href]"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_selectors() {
+        for source in [
+            "",
+            "a >",
+            "a,",
+            "[href",
+            "[href=]",
+            "a[href!=x]",
+            "a:hover",
+            ":not(a",
+            "a)",
+            "a[href=\"x]",
+        ] {
+            assert!(Selector::parse(source).is_err(), "'{source}' should be rejected");
+        }
+    }
+}
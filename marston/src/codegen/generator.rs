@@ -57,6 +57,7 @@ impl Gen for IrNode {
                 }
             }
             IrNode::Text(text) => p.writeln(&escape(text).to_string()),
+            IrNode::Raw(text) => p.writeln(text),
         }
     }
 }
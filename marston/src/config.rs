@@ -1,29 +1,400 @@
-use crate::{MPath, MResult, fs::walk_for_file};
+use crate::{
+    MPath, MResult,
+    fs::walk_for_file,
+    html::head::{HeadMapping, HeadTarget, SPECIAL_HEAD_ATTRIBUTES, builtin_head_mappings},
+    site::route::is_route_template,
+};
 use anyhow::anyhow;
 use fs_err::read_to_string;
+use glob::Pattern;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use url::Url;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParsedConfig {
     pub project: ProjectConfig,
     pub build: Option<BuildConfig>,
+    #[serde(default)]
+    pub rules: Vec<RuleConfig>,
+    #[serde(default)]
+    pub head: Vec<HeadMappingConfig>,
+    pub robots: Option<RobotsConfig>,
+    #[serde(default)]
+    pub feeds: Vec<FeedConfig>,
+    #[serde(default)]
+    pub collections: Vec<CollectionConfig>,
+    #[serde(default)]
+    pub routes: Vec<RouteConfig>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub project: ProjectConfig,
     pub build: BuildConfig,
+    pub rules: Vec<RuleConfig>,
+    pub head: Vec<HeadMappingConfig>,
+    /// `robots.txt` is only generated if the section is present
+    pub robots: Option<RobotsConfig>,
+    pub feeds: Vec<FeedConfig>,
+    pub collections: Vec<CollectionConfig>,
+    pub routes: Vec<RouteConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectConfig {
     pub name: String,
+    /// Absolute URL the site is served from, e.g. `https://example.com/`. Always ends with '/'
+    /// once the config is loaded.
+    pub base_url: Option<Url>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildConfig {
     pub output_dir: MPath,
     pub main_dir: MPath,
+    /// Directory of the data files pages can reference, see [`crate::data`]. Defaults to the
+    /// `data` directory of the project.
+    #[serde(default)]
+    pub data_dir: MPath,
+    /// Writes `about.mr` to `about/index.html` instead of `about.html`, pages can override it
+    /// with `.page(.pretty_url = ...)`
+    #[serde(default)]
+    pub pretty_urls: bool,
+    /// Directory the site is served from, e.g. `/pr-123` for `https://host/pr-123/`. Root-relative
+    /// urls are prefixed with it. Set by `--base-path` as well.
+    pub base_path: Option<String>,
+    /// Adds a `<base>` pointing to the directory of the page under `base_path`. Links to
+    /// fragments of the page itself are written with its file name so they stay on the page.
+    #[serde(default)]
+    pub emit_base: bool,
+}
+
+/// A validation rule declared in the `[[rules]]` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleConfig {
+    /// Block the rule applies to. Rules without an element apply to every block.
+    pub element: Option<String>,
+    /// Path of parent blocks starting at the document root, e.g. `["body", "main"]`. Needs an
+    /// `element`.
+    #[serde(default)]
+    pub parent: Vec<String>,
+    #[serde(default)]
+    pub required_attributes: Vec<String>,
+    #[serde(default)]
+    pub disallowed_attributes: Vec<String>,
+    /// Attribute that `allowed_values`, `type` and the length checks apply to
+    pub attribute: Option<String>,
+    pub allowed_values: Option<Vec<String>>,
+    #[serde(rename = "type")]
+    pub value_type: Option<RuleValueType>,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    #[serde(default)]
+    pub severity: RuleSeverity,
+    pub message: Option<String>,
+}
+
+impl RuleConfig {
+    fn check(&self) -> MResult<()> {
+        if self.element.is_none() && !self.required_attributes.is_empty() {
+            return Err(anyhow!("rules with `required_attributes` must specify an `element`"));
+        }
+
+        if self.element.is_none() && !self.parent.is_empty() {
+            return Err(anyhow!("rules with a `parent` must specify an `element`"));
+        }
+
+        let has_value_checks = self.allowed_values.is_some()
+            || self.value_type.is_some()
+            || self.min_length.is_some()
+            || self.max_length.is_some();
+        if self.attribute.is_none() && has_value_checks {
+            return Err(anyhow!(
+                "rules with `allowed_values`, `type`, `min_length` or `max_length` must specify an `attribute`"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// A `.head` attribute declared in the `[[head]]` table, rendered as the one tag that is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeadMappingConfig {
+    /// Attribute of `.head`, e.g. `google_site_verification`
+    pub attribute: String,
+    /// Renders `<meta name="..." content="...">`
+    pub meta: Option<String>,
+    /// Renders `<meta property="..." content="...">`
+    pub property: Option<String>,
+    /// Renders `<meta http-equiv="..." content="...">`
+    pub http_equiv: Option<String>,
+    /// Renders `<link rel="..." href="...">`
+    pub link: Option<String>,
+}
+
+impl HeadMappingConfig {
+    fn check(&self) -> MResult<()> {
+        let mut chars = self.attribute.chars();
+        let is_identifier = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_identifier {
+            return Err(anyhow!(
+                "head mapping attribute '{}' must be a valid attribute name, use '_' instead of '-'",
+                self.attribute
+            ));
+        }
+
+        if SPECIAL_HEAD_ATTRIBUTES.contains(&self.attribute.as_str()) {
+            return Err(anyhow!("head attribute '{}' can't be remapped", self.attribute));
+        }
+
+        let targets = [&self.meta, &self.property, &self.http_equiv, &self.link];
+        if targets.iter().filter(|target| target.is_some()).count() != 1 {
+            return Err(anyhow!(
+                "head mapping for '{}' must set exactly one of `meta`, `property`, `http_equiv` or `link`",
+                self.attribute
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn to_mapping(&self) -> HeadMapping {
+        let target = match (&self.meta, &self.property, &self.http_equiv, &self.link) {
+            (Some(name), ..) => HeadTarget::Meta(name.clone()),
+            (_, Some(name), ..) => HeadTarget::Property(name.clone()),
+            (_, _, Some(name), _) => HeadTarget::HttpEquiv(name.clone()),
+            (.., Some(rel)) => HeadTarget::Link(rel.clone()),
+            (None, None, None, None) => unreachable!("head mappings are checked when loading"),
+        };
+
+        HeadMapping::new(&self.attribute, target)
+    }
+}
+
+/// The `[robots]` section, written to `robots.txt` in the build directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RobotsConfig {
+    /// Groups of `[[robots.rules]]`. Without any, every crawler is allowed everywhere.
+    #[serde(default)]
+    pub rules: Vec<RobotsRule>,
+    /// Points crawlers to the generated sitemap, requires `project.base_url`
+    #[serde(default = "default_true")]
+    pub sitemap: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RobotsRule {
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
+    /// Paths the crawler may visit even if they are inside a disallowed path
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub disallow: Vec<String>,
+    /// Seconds to wait between requests, not supported by every crawler
+    pub crawl_delay: Option<u32>,
+}
+
+impl RobotsRule {
+    fn check(&self) -> MResult<()> {
+        if self.user_agent.trim().is_empty() {
+            return Err(anyhow!("robots rules must have a non-empty `user_agent`"));
+        }
+
+        if let Some(path) =
+            self.allow.iter().chain(&self.disallow).find(|path| !path.starts_with('/'))
+        {
+            return Err(anyhow!(
+                "robots rule path '{path}' for '{}' must start with '/'",
+                self.user_agent
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// A feed declared in the `[[feeds]]` table. Entries are read from the `title`, `description`,
+/// `date` and `author` attributes of each page's `.head`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedConfig {
+    pub title: String,
+    pub description: Option<String>,
+    /// Author of the feed, for entries without their own
+    pub author: Option<String>,
+    /// A directory, e.g. `blog`, or a glob matched against page urls, e.g. `blog/*.html`
+    pub pages: String,
+    /// Path of the Atom feed in the build directory, e.g. `feed.xml`
+    pub atom: Option<String>,
+    /// Path of the RSS feed in the build directory, e.g. `rss.xml`
+    pub rss: Option<String>,
+    /// Number of most recent entries to keep
+    #[serde(default = "default_feed_limit")]
+    pub limit: usize,
+}
+
+impl FeedConfig {
+    fn check(&self) -> MResult<()> {
+        if self.atom.is_none() && self.rss.is_none() {
+            return Err(anyhow!(
+                "feed '{}' must set `atom`, `rss` or both to the file it is written to",
+                self.title
+            ));
+        }
+
+        Pattern::new(&self.pages).map_err(|err| {
+            anyhow!("feed '{}' has an invalid `pages` pattern '{}': {err}", self.title, self.pages)
+        })?;
+
+        Ok(())
+    }
+
+    /// Whether the page served at `url` belongs to the feed.
+    pub fn matches(&self, url: &str) -> bool {
+        if self.pages.contains(['*', '?', '[']) {
+            return Pattern::new(&self.pages).is_ok_and(|pattern| pattern.matches(url));
+        }
+
+        let dir = self.pages.trim_matches('/');
+        dir.is_empty() || url.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/'))
+    }
+}
+
+/// A directory of pages declared in the `[[collections]]` table, listed by `.collection` blocks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionConfig {
+    pub name: String,
+    /// Directory of the pages relative to the main directory, e.g. `posts`
+    pub dir: String,
+    #[serde(default)]
+    pub sort_by: CollectionSort,
+    /// Defaults to newest first when sorting by date and ascending otherwise
+    pub order: Option<SortOrder>,
+    /// Entries per page of lists with `.paginate = true`
+    pub page_size: Option<usize>,
+}
+
+impl CollectionConfig {
+    fn check(&self) -> MResult<()> {
+        if self.page_size == Some(0) {
+            return Err(anyhow!("collection '{}' must have a `page_size` above 0", self.name));
+        }
+
+        Ok(())
+    }
+
+    /// Whether the page served at `url` is an entry. The index page of the directory lists the
+    /// entries and is not one of them.
+    pub fn contains(&self, url: &str) -> bool {
+        let dir = self.dir.trim_matches('/');
+        let rest = if dir.is_empty() { Some(url) } else { url.strip_prefix(&format!("{dir}/")) };
+
+        rest.is_some_and(|rest| rest != "index.html")
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollectionSort {
+    #[default]
+    Date,
+    Title,
+    Author,
+    Url,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// A template declared in the `[[routes]]` table, rendered once for every record of `data`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteConfig {
+    /// Path of the template relative to the main directory, e.g. `products/[slug].mr`. Every
+    /// `[field]` is replaced with the field of the record.
+    pub template: String,
+    /// Data the records are read from, e.g. `shop.products` for `data/shop/products.csv`
+    pub data: String,
+}
+
+impl RouteConfig {
+    fn check(&self) -> MResult<()> {
+        if MPath::from(&self.template).extension() != Some("mr") {
+            return Err(anyhow!("route template '{}' must be a `.mr` file", self.template));
+        }
+        if !is_route_template(&self.template) {
+            return Err(anyhow!(
+                "route template '{}' needs a `[field]` in its path, e.g. `products/[slug].mr`",
+                self.template
+            ));
+        }
+        if self.data.trim().is_empty() {
+            return Err(anyhow!("route '{}' must name its `data`", self.template));
+        }
+
+        Ok(())
+    }
+
+    /// Whether `path`, relative to the main directory, is the template of this route.
+    pub fn is_template(&self, path: &str) -> bool {
+        self.template.trim_start_matches('/') == path
+    }
+}
+
+/// `base_path` as `/dir`, without a trailing '/'. `None` for the root of the host.
+pub fn normalize_base_path(path: &str) -> MResult<Option<String>> {
+    let trimmed = path.trim().trim_matches('/');
+    if trimmed.contains(['?', '#', '\\', ':']) || trimmed.contains(char::is_whitespace) {
+        return Err(anyhow!(
+            "`build.base_path` must be a path like `/docs`, found '{path}'. Use `project.base_url` for the host"
+        ));
+    }
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    if trimmed.split('/').any(|segment| matches!(segment, "" | "." | "..")) {
+        return Err(anyhow!(
+            "`build.base_path` can't contain empty, '.' or '..' segments, found '{path}'"
+        ));
+    }
+
+    Ok(Some(format!("/{trimmed}")))
+}
+
+const fn default_feed_limit() -> usize {
+    20
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+fn default_user_agent() -> String {
+    "*".to_string()
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleValueType {
+    String,
+    Number,
+    Boolean,
+    Array,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleSeverity {
+    #[default]
+    Error,
+    Warning,
+    Advice,
 }
 
 impl Config {
@@ -33,15 +404,197 @@ impl Config {
         let file = walk_for_file(cwd.into(), Self::FILE_NAME).ok_or_else(|| {
             anyhow!("No config file found in {} or any of its parents", cwd.to_string())
         })?;
-        let content = read_to_string(file)?;
-        Ok(Config::fill_defaults(toml::from_str::<ParsedConfig>(&content)?, cwd))
+        Self::parse(&read_to_string(file)?, cwd)
+    }
+
+    fn parse(content: &str, cwd: &MPath) -> MResult<Self> {
+        let mut config = toml::from_str::<ParsedConfig>(content)?;
+
+        for rule in &config.rules {
+            rule.check()?;
+        }
+
+        for mapping in &config.head {
+            mapping.check()?;
+        }
+
+        for rule in config.robots.iter().flat_map(|robots| &robots.rules) {
+            rule.check()?;
+        }
+
+        for feed in &config.feeds {
+            feed.check()?;
+        }
+        for collection in &config.collections {
+            collection.check()?;
+        }
+        if let Some(duplicate) = config.collections.iter().map(|c| &c.name).duplicates().next() {
+            return Err(anyhow!("collection '{duplicate}' is declared more than once"));
+        }
+
+        for route in &config.routes {
+            route.check()?;
+        }
+        if let Some(duplicate) = config.routes.iter().map(|r| &r.template).duplicates().next() {
+            return Err(anyhow!("route template '{duplicate}' is declared more than once"));
+        }
+
+        if let Some(build) = &mut config.build
+            && let Some(base_path) = &build.base_path
+        {
+            build.base_path = normalize_base_path(base_path)?;
+        }
+
+        // pages are joined onto the base url, which drops its last segment without a '/'
+        if let Some(base_url) = &mut config.project.base_url
+            && !base_url.path().ends_with('/')
+        {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        if !config.feeds.is_empty() && config.project.base_url.is_none() {
+            return Err(anyhow!("feeds need `project.base_url` to link to their entries"));
+        }
+
+        Ok(Config::fill_defaults(config, cwd))
     }
 
     fn fill_defaults(config: ParsedConfig, cwd: &MPath) -> Self {
-        let build = config
-            .build
-            .unwrap_or(BuildConfig { output_dir: cwd.join("dist"), main_dir: cwd.join("src") });
+        let mut build = config.build.unwrap_or(BuildConfig {
+            output_dir: cwd.join("dist"),
+            main_dir: cwd.join("src"),
+            data_dir: cwd.join("data"),
+            pretty_urls: false,
+            base_path: None,
+            emit_base: false,
+        });
+        // left out of `[build]`
+        if build.data_dir.as_str().is_empty() {
+            build.data_dir = cwd.join("data");
+        }
+
+        Self {
+            project: config.project,
+            build,
+            rules: config.rules,
+            head: config.head,
+            robots: config.robots,
+            feeds: config.feeds,
+            collections: config.collections,
+            routes: config.routes,
+        }
+    }
+
+    /// The built-in head mappings, with entries from `[[head]]` replacing or extending them.
+    pub fn head_mappings(&self) -> Vec<HeadMapping> {
+        let mut mappings = builtin_head_mappings();
+
+        for mapping in self.head.iter().map(HeadMappingConfig::to_mapping) {
+            match mappings.iter_mut().find(|m| m.attribute == mapping.attribute) {
+                Some(existing) => *existing = mapping,
+                None => mappings.push(mapping),
+            }
+        }
+
+        mappings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base_url(base_url: &str) -> Url {
+        let content = format!("[project]\nname = \"site\"\nbase_url = \"{base_url}\"\n");
+        Config::parse(&content, &MPath::from("/site")).unwrap().project.base_url.unwrap()
+    }
+
+    fn data_dir(build: &str) -> MPath {
+        let content = format!("[project]\nname = \"site\"\n{build}");
+        Config::parse(&content, &MPath::from("/site")).unwrap().build.data_dir
+    }
+
+    #[test]
+    fn data_dir_defaults_to_the_project() {
+        assert_eq!(data_dir(""), "/site/data");
+        assert_eq!(data_dir("[build]\noutput_dir = \"out\"\nmain_dir = \"pages\"\n"), "/site/data");
+        assert_eq!(
+            data_dir(
+                "[build]\noutput_dir = \"out\"\nmain_dir = \"pages\"\ndata_dir = \"content\"\n"
+            ),
+            "content"
+        );
+    }
+
+    #[test]
+    fn base_urls_are_directories() {
+        assert_eq!(base_url("https://example.com").as_str(), "https://example.com/");
+        assert_eq!(base_url("https://example.com/docs").as_str(), "https://example.com/docs/");
+        assert_eq!(base_url("https://example.com/docs/").as_str(), "https://example.com/docs/");
+        assert_eq!(
+            base_url("https://example.com/docs").join("blog/post.html").unwrap().as_str(),
+            "https://example.com/docs/blog/post.html"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for (rule, error) in [
+            ("required_attributes = [\"alt\"]", "must specify an `element`"),
+            (
+                "parent = [\"body\"]\ndisallowed_attributes = [\"style\"]",
+                "must specify an `element`",
+            ),
+            ("element = \"img\"\nallowed_values = [\"lazy\"]", "must specify an `attribute`"),
+            ("element = \"img\"\ntype = \"string\"", "must specify an `attribute`"),
+            ("element = \"img\"\nmin_length = 1", "must specify an `attribute`"),
+            ("element = \"img\"\nattribute = \"alt\"\ntype = \"text\"", "unknown variant"),
+            ("element = \"img\"\nseverity = \"fatal\"", "unknown variant"),
+        ] {
+            let content = format!("[project]\nname = \"site\"\n\n[[rules]]\n{rule}\n");
+            let err = Config::parse(&content, &MPath::from("/site")).unwrap_err().to_string();
+            assert!(err.contains(error), "'{rule}' failed with '{err}'");
+        }
+    }
+
+    #[test]
+    fn accepts_valid_rules() {
+        let content = r#"
+            [project]
+            name = "site"
+
+            [[rules]]
+            element = "img"
+            parent = ["body"]
+            required_attributes = ["alt"]
+            attribute = "loading"
+            allowed_values = ["lazy", "eager"]
+        "#;
+        assert_eq!(Config::parse(content, &MPath::from("/site")).unwrap().rules.len(), 1);
+    }
+
+    #[test]
+    fn normalizes_base_paths() {
+        assert_eq!(normalize_base_path("/docs").unwrap().as_deref(), Some("/docs"));
+        assert_eq!(normalize_base_path("docs/").unwrap().as_deref(), Some("/docs"));
+        assert_eq!(normalize_base_path(" /docs/v2/ ").unwrap().as_deref(), Some("/docs/v2"));
+        assert_eq!(normalize_base_path("").unwrap(), None);
+        assert_eq!(normalize_base_path("/").unwrap(), None);
+    }
 
-        Self { project: config.project, build }
+    #[test]
+    fn rejects_invalid_base_paths() {
+        for path in [
+            "https://example.com/docs",
+            "/docs?v=2",
+            "/docs#top",
+            "\\docs",
+            "/my docs",
+            "/docs//v2",
+            "/docs/./v2",
+            "/../docs",
+        ] {
+            assert!(normalize_base_path(path).is_err(), "'{path}' should be rejected");
+        }
     }
 }
@@ -1,17 +1,39 @@
 use crate::{
-    MPath, MResult,
+    MPath, MResult, Span,
     ast::{MarstonDocument, ident_table::IdentTable, parser::Parser},
     codegen::{Codegen, Gen},
-    config::Config,
+    config::{Config, normalize_base_path},
+    data::DataSet,
     fs::read_string,
-    html::ir::ToHtmlIR,
+    html::{head::HeadMapping, ir::ToHtmlIR},
     info::{Info, InfoWalker},
     lexer::{Token, TokenKind},
+    plugin::Plugin,
+    report,
     reports::ReportsBag,
-    validator::Validate,
+    site::{
+        Page, Site,
+        collection::{CollectionTransform, page_count, paginated_url},
+        feed::{feed_entries, generate_atom, generate_rss, validate_feed_pages},
+        robots::{ROBOTS_FILE, generate_robots},
+        route::{is_route_template, route_pages},
+        sitemap::{SITEMAP_FILE, generate_sitemap},
+        url_dir,
+        urls::{BasePathTransform, LinkTransform, colliding_urls},
+    },
+    validator::{
+        Validate,
+        rules::{
+            collection::validate_collections,
+            custom::validate_custom_rules,
+            head::{validate_emitted_base, validate_unmapped_head_attributes},
+        },
+    },
 };
-use log::error;
+use ariadne::{Report, ReportKind};
+use log::{error, info, warn};
 use logos::Logos;
+use rustc_hash::FxHashSet;
 use std::sync::Arc;
 
 #[derive(Debug)]
@@ -19,15 +41,38 @@ pub struct Context {
     config: Config,
     cwd: MPath,
     current_file: Option<Arc<MPath>>,
+    site: Site,
+    /// Files of the data directory, referenced from pages
+    data: Arc<DataSet>,
+    /// Parsed pages without errors, written in `finish`
+    pending: Vec<PendingPage>,
+    /// Built-in and configured `.head` attributes that are rendered as tags
+    head_mappings: Vec<HeadMapping>,
+    plugins: Vec<Box<dyn Plugin>>,
     pub had_errors: bool,
 }
 
+#[derive(Debug)]
+struct PendingPage {
+    /// Output path relative to the build directory
+    url: String,
+    doc: MarstonDocument,
+}
+
 impl Context {
     pub fn new(cwd: &MPath) -> MResult<Self> {
+        let config = Config::find_recursively(cwd)?;
+        let data = Arc::new(DataSet::load(&config.build.data_dir)?);
+
         Ok(Context {
-            config: Config::find_recursively(cwd)?,
+            head_mappings: config.head_mappings(),
+            config,
             cwd: cwd.clone(),
             current_file: None,
+            site: Site::new(),
+            data,
+            pending: vec![],
+            plugins: vec![],
             had_errors: false,
         })
     }
@@ -44,23 +89,38 @@ impl Context {
         &self.config.build.main_dir
     }
 
+    /// Overrides `build.base_path`, e.g. to build a preview served from a directory.
+    pub fn set_base_path(&mut self, path: &str) -> MResult<()> {
+        self.config.build.base_path = normalize_base_path(path)?;
+        Ok(())
+    }
+
+    /// Registers a plugin whose rules and transformations run for every file processed afterwards.
+    pub fn register_plugin(&mut self, plugin: impl Plugin + 'static) {
+        self.plugins.push(Box::new(plugin));
+    }
+
+    pub fn plugins(&self) -> &[Box<dyn Plugin>] {
+        &self.plugins
+    }
+
     pub fn file(&self) -> Arc<MPath> {
         self.current_file.clone().unwrap()
     }
 
     pub fn process_file(&mut self, file: &MPath) -> MResult<()> {
         self.current_file = Some(Arc::new(file.clone()));
-        let content = read_string(file)?;
+        let content = Arc::<str>::from(read_string(file)?);
 
-        ReportsBag::init(self.current_file.clone().unwrap(), Arc::<str>::from(content.clone()));
+        ReportsBag::init(self.current_file.clone().unwrap(), Arc::clone(&content));
         let tokens = TokenKind::get_tokens(&content);
 
         let mut parser = Parser::new(self, tokens);
         parser.parse();
         let mut doc = parser.doc.clone();
 
-        let file_name = file.strip_prefix(self.main_dir())?;
-        let file = self.build_dir().join(file_name).with_extension("html");
+        let path = file.strip_prefix(self.main_dir())?.as_str().replace('\\', "/");
+        let file_name = file.strip_prefix(self.main_dir())?.with_extension("html");
         ReportsBag::print();
 
         if ReportsBag::has_reports() {
@@ -69,22 +129,195 @@ impl Context {
         }
         ReportsBag::clear_errors();
 
-        let info = &mut Info::new();
+        let url = file_name.as_str().replace('\\', "/");
+        if is_route_template(&path) {
+            self.process_route(&path, &url, &doc, &content);
+            return Ok(());
+        }
+
+        self.data.resolve(&mut doc, None);
+        self.add_page(doc, content, url);
+        ReportsBag::print();
+
+        Ok(())
+    }
+
+    /// Generates a page from the template at `path` for every record of its route. Reports shared
+    /// by the pages are printed once.
+    fn process_route(
+        &mut self,
+        path: &str,
+        url: &str,
+        template: &MarstonDocument,
+        content: &Arc<str>,
+    ) {
+        let Some(route) = self.config.routes.iter().find(|route| route.is_template(path)).cloned()
+        else {
+            ReportsBag::add(report!(
+                kind: ReportKind::Error,
+                message: format!("No route for the template '{path}'"),
+                notes: [format!("Declare the data to generate pages from in `marston.toml` with a `[[routes]]` entry with `template = \"{path}\"`")]
+            ));
+            ReportsBag::print();
+            self.had_errors = true;
+            return;
+        };
+
+        let data = Arc::clone(&self.data);
+        let pages = route_pages(&route, url, &data);
+        ReportsBag::print();
+        if ReportsBag::has_errors() {
+            self.had_errors = true;
+        }
+
+        let mut seen = FxHashSet::default();
+        for page in pages {
+            ReportsBag::init(self.file(), Arc::clone(content));
+
+            let mut doc = template.clone();
+            data.resolve(&mut doc, Some(&page.record));
+            self.add_page(doc, Arc::clone(content), page.url.clone());
+
+            ReportsBag::print_unseen(&mut seen, || {
+                info!("{} generated from {path} for '{}'", page.url, page.record.path);
+            });
+        }
+    }
+
+    /// Validates `doc` and adds it to the site. Pages without errors are written in `finish`.
+    fn add_page(&mut self, mut doc: MarstonDocument, content: Arc<str>, url: String) {
+        let info = &mut Info::new(
+            self.file(),
+            self.main_dir().clone(),
+            self.config.project.base_url.clone(),
+        );
         doc.collect_info(info);
 
+        // custom and plugin rules go first so their validators join the single walk in `validate`
+        validate_custom_rules(info, &self.config.rules);
+        validate_collections(&doc, info, &self.config.collections);
+        validate_unmapped_head_attributes(&doc, &self.head_mappings);
+        if self.config.build.emit_base && self.config.build.base_path.is_some() {
+            validate_emitted_base(&doc, info);
+        }
+
+        for plugin in &self.plugins {
+            for rule in plugin.rules() {
+                rule(&doc, info);
+            }
+        }
+
         doc.validate(info);
 
-        ReportsBag::print();
+        let mut page =
+            Page::new(self.file(), content, url, &doc, info, self.config.build.pretty_urls);
+
+        if ReportsBag::has_errors() {
+            self.had_errors = true;
+        } else if page.front_matter.draft {
+            info!("Skipping the draft {}", page.url);
+        } else {
+            page.emitted = true;
+            self.pending.push(PendingPage { url: page.url.clone(), doc });
+        }
+
+        self.site.add_page(page);
+    }
+
+    /// Writes the pages without errors once every page is known, so lists can include all of
+    /// them.
+    fn render_pages(&mut self) -> MResult<()> {
+        for pending in std::mem::take(&mut self.pending) {
+            let Some(page) = self.site.pages().iter().find(|page| page.url == pending.url) else {
+                continue;
+            };
+            let ir = pending.doc.to_html_ir(&self.head_mappings);
+            let pages = page_count(&ir, &self.site, &self.config.collections);
 
-        let has_errors = ReportsBag::has_errors();
+            for number in 1..=pages {
+                let url = paginated_url(&pending.url, number);
+                let mut ir = ir.clone();
+                ir.apply(&LinkTransform { site: &self.site, page, dir: url_dir(&url) });
+                ir.apply(&CollectionTransform {
+                    site: &self.site,
+                    configs: &self.config.collections,
+                    url: &pending.url,
+                    number,
+                    pages,
+                });
+                for plugin in &self.plugins {
+                    for transformation in plugin.transformations() {
+                        ir.apply(transformation.as_ref());
+                    }
+                }
+                // last, so links added by lists and plugins are prefixed too
+                if let Some(base_path) = &self.config.build.base_path {
+                    ir.apply(&BasePathTransform {
+                        base_path,
+                        url: &url,
+                        emit_base: self.config.build.emit_base,
+                    });
+                }
 
-        if has_errors || self.had_errors {
+                let codegen = &mut Codegen::new();
+                ir.generate(codegen);
+                codegen.write_to_file(&self.build_dir().join(&url))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the pages, runs the checks that need every page of the site and prints their
+    /// reports once, then writes the files generated from all pages.
+    pub fn finish(&mut self) -> MResult<()> {
+        let mut reports = self.site.validate(&self.config.collections);
+
+        // pages sharing an output path are reported instead of overwriting each other
+        let collisions = colliding_urls(&self.site);
+        self.pending.retain(|pending| !collisions.contains(&pending.url));
+        self.site.withdraw(&collisions);
+
+        self.render_pages()?;
+        validate_feed_pages(&self.site, &self.config.feeds, &mut reports);
+        reports.print(self.site.sources());
+
+        if reports.has_errors() {
             self.had_errors = true;
+        }
+
+        let base_url = self.config.project.base_url.clone();
+        let sitemap = if let Some(base_url) = &base_url {
+            generate_sitemap(&self.site, base_url)
+                .write_to_file(&self.build_dir().join(SITEMAP_FILE))?;
+
+            // feeds without a base_url are rejected when the config is loaded
+            for feed in &self.config.feeds {
+                let entries = feed_entries(&self.site, feed);
+                if let Some(path) = &feed.atom {
+                    generate_atom(feed, &entries, base_url)
+                        .write_to_file(&self.build_dir().join(path))?;
+                }
+                if let Some(path) = &feed.rss {
+                    generate_rss(feed, &entries, base_url)
+                        .write_to_file(&self.build_dir().join(path))?;
+                }
+            }
+
+            base_url.join(SITEMAP_FILE).ok()
         } else {
-            let ir = doc.to_html_ir();
-            let codegen = &mut Codegen::new();
-            ir.generate(codegen);
-            codegen.write_to_file(&file)?;
+            // only robots.txt asks for it here, feeds need a base_url to load the config
+            if self.config.robots.as_ref().is_some_and(|robots| robots.sitemap) {
+                warn!(
+                    "No sitemap generated for `robots.sitemap`, set `project.base_url` to the url the site is served from"
+                );
+            }
+            None
+        };
+
+        if let Some(robots) = &self.config.robots {
+            let sitemap = sitemap.as_ref().filter(|_| robots.sitemap);
+            generate_robots(robots, sitemap).write_to_file(&self.build_dir().join(ROBOTS_FILE))?;
         }
 
         Ok(())
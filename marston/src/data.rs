@@ -0,0 +1,824 @@
+//! Data files in the `data` directory, loaded once per build and referenced from pages.
+//!
+//! `data/authors.toml` is referenced as `{data.authors}` and `data/shop/products.json` as
+//! `{data.shop.products}`. Keys of tables and indexes of arrays follow after further dots, e.g.
+//! `{data.authors.jane.name}` or `{data.links.0.url}`. CSV files are tables keyed by the first
+//! column, whose rows are tables of the cells keyed by the header.
+//!
+//! An attribute value that is a single reference takes the type of the value, so
+//! `.width = "{data.images.logo.width}"` is a number. Anywhere else the value is inserted as
+//! text. JSON `null` is an empty string.
+//!
+//! Pages generated by a route reference the record they are generated for as `{item.key}`.
+
+use crate::{
+    MPath, MResult, Span,
+    ast::{Block, MarstonDocument, Node, Value, ValueKind},
+    fs::{read_string, to_mpath},
+    reports::{MReport, ReportsBag},
+    suggestions::did_you_mean,
+};
+use anyhow::anyhow;
+use ariadne::{Color, Label, Report, ReportBuilder, ReportKind};
+use glob::glob;
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::sync::Arc;
+
+const EXTENSIONS: &[&str] = &["toml", "json", "csv"];
+
+const DATA_SCOPE: &str = "data";
+const ITEM_SCOPE: &str = "item";
+
+/// `{data.file.key}` or `{item.key}`, capturing the scope and the path after it
+static REFERENCE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{(data|item)\.([^{}\s]+)\}").unwrap());
+
+#[derive(Debug, Default)]
+pub struct DataSet {
+    dir: MPath,
+    files: Vec<DataFile>,
+}
+
+#[derive(Debug)]
+struct DataFile {
+    /// Dotted path the file is referenced by, e.g. `shop.products`
+    name: String,
+    path: Arc<MPath>,
+    content: Arc<str>,
+    root: DataNode,
+}
+
+#[derive(Debug)]
+struct DataNode {
+    value: DataValue,
+    /// Key the value is declared with, or the value itself for items of arrays
+    span: Span,
+}
+
+#[derive(Debug)]
+enum DataValue {
+    String(String),
+    Number(f64),
+    Boolean(bool),
+    Array(Vec<DataNode>),
+    Table(Vec<(String, DataNode)>),
+}
+
+impl DataValue {
+    fn kind(&self) -> &'static str {
+        match self {
+            DataValue::String(_) => "a string",
+            DataValue::Number(_) => "a number",
+            DataValue::Boolean(_) => "a boolean",
+            DataValue::Array(_) => "an array",
+            DataValue::Table(_) => "a table",
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        match self {
+            DataValue::Table(entries) => entries.iter().map(|(key, _)| key.as_str()).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Tables and arrays containing tables have no [`ValueKind`].
+    fn to_value_kind(&self, span: &Span) -> Option<ValueKind> {
+        Some(match self {
+            DataValue::String(s) => ValueKind::String(s.clone()),
+            DataValue::Number(n) => ValueKind::Number(*n),
+            DataValue::Boolean(b) => ValueKind::Boolean(*b),
+            DataValue::Array(items) => ValueKind::Array(
+                items
+                    .iter()
+                    .map(|item| {
+                        Some(Value { kind: item.value.to_value_kind(span)?, span: span.clone() })
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+            DataValue::Table(_) => return None,
+        })
+    }
+}
+
+/// A value found for a reference, with the key it is declared with in `file`.
+#[derive(Debug, Clone)]
+pub struct Found<'a> {
+    file: &'a DataFile,
+    node: &'a DataNode,
+    /// Reference of the value, e.g. `data.shop.products.widget`
+    pub path: String,
+}
+
+impl Found<'_> {
+    /// Where the value is declared, to report problems of the value itself.
+    pub fn location(&self) -> (Arc<MPath>, Span) {
+        (Arc::clone(&self.file.path), self.node.span.clone())
+    }
+
+    /// Label at the key of the value, its file is printed along with the current file.
+    pub fn label(&self, message: String) -> Label<(Arc<MPath>, Span)> {
+        ReportsBag::add_source(Arc::clone(&self.file.path), Arc::clone(&self.file.content));
+        Label::new(self.location()).with_message(message).with_color(Color::BrightYellow)
+    }
+
+    /// `key` of a table written as text, `None` if it is missing or not a single value.
+    pub fn text(&self, key: &str) -> Option<String> {
+        let DataValue::Table(entries) = &self.node.value else {
+            return None;
+        };
+        let (_, node) = entries.iter().find(|(name, _)| name == key)?;
+        match node.value.to_value_kind(&Span::default())? {
+            ValueKind::Array(_) => None,
+            kind => Some(kind.to_string()),
+        }
+    }
+}
+
+impl DataSet {
+    /// Loads every TOML, JSON and CSV file below `dir`. A missing directory has no data.
+    pub fn load(dir: &MPath) -> MResult<Self> {
+        let mut data = Self { dir: dir.clone(), files: vec![] };
+        if !dir.is_dir() {
+            return Ok(data);
+        }
+
+        let mut paths = glob(dir.join("**").join("*").as_str())?
+            .filter_map(Result::ok)
+            .filter_map(|path| to_mpath(path).ok())
+            .filter(|path| path.extension().is_some_and(|ext| EXTENSIONS.contains(&ext)))
+            .collect_vec();
+        paths.sort();
+
+        for path in paths {
+            let name = path.strip_prefix(dir)?.with_extension("").components().join(".");
+            if let Some(other) = data.files.iter().find(|file| file.name == name) {
+                return Err(anyhow!(
+                    "data files {} and {path} are both referenced as 'data.{name}'",
+                    other.path
+                ));
+            }
+
+            let content = Arc::<str>::from(read_string(&path)?);
+            let root = match path.extension() {
+                Some("toml") => parse_toml(&content),
+                Some("json") => parse_json(&content),
+                _ => parse_csv(&content),
+            }
+            .map_err(|err| anyhow!("failed to read data file {path}: {err}"))?;
+
+            data.files.push(DataFile { name, path: Arc::new(path), content, root });
+        }
+
+        Ok(data)
+    }
+
+    /// Replaces the references in attribute values and text of `doc`, `item` being the record a
+    /// route page is generated for. References that can't be resolved are reported and left as
+    /// written.
+    pub fn resolve(&self, doc: &mut MarstonDocument, item: Option<&Found>) {
+        for block in &mut doc.blocks {
+            self.resolve_block(block, item);
+        }
+    }
+
+    fn resolve_block(&self, block: &mut Block, item: Option<&Found>) {
+        for attr in &mut block.attributes {
+            self.resolve_value(&mut attr.value, item);
+        }
+
+        // text keeps no span, so references in it are reported at the block
+        let span = block.name.as_ref().map_or_else(|| block.span.clone(), |name| name.span.clone());
+        for child in &mut block.children {
+            match child {
+                Node::Block(child) => self.resolve_block(child, item),
+                Node::Text(text) => {
+                    if let Some(filled) = self.interpolate(text, item, &span) {
+                        *text = filled;
+                    }
+                }
+            }
+        }
+    }
+
+    fn resolve_value(&self, value: &mut Value, item: Option<&Found>) {
+        match &mut value.kind {
+            ValueKind::String(text) => {
+                let single = REFERENCE
+                    .captures(text.trim())
+                    .filter(|captures| captures[0].len() == text.trim().len())
+                    .map(|captures| (captures[1].to_string(), captures[2].to_string()));
+
+                if let Some((scope, path)) = single {
+                    let Some(found) = self.lookup(&scope, &path, item, Some(&value.span)) else {
+                        return;
+                    };
+                    match found.node.value.to_value_kind(&value.span) {
+                        Some(kind) => value.kind = kind,
+                        None => report_not_a_value(&found, &value.span),
+                    }
+                } else if let Some(filled) = self.interpolate(text, item, &value.span) {
+                    *text = filled;
+                }
+            }
+            ValueKind::Array(values) => {
+                for value in values {
+                    self.resolve_value(value, item);
+                }
+            }
+            ValueKind::Number(_) | ValueKind::Boolean(_) => {}
+        }
+    }
+
+    /// `text` with its references replaced, `None` if it has none.
+    fn interpolate(&self, text: &str, item: Option<&Found>, span: &Span) -> Option<String> {
+        if !REFERENCE.is_match(text) {
+            return None;
+        }
+
+        let filled = REFERENCE.replace_all(text, |captures: &regex::Captures| {
+            let found = self.lookup(&captures[1], &captures[2], item, Some(span));
+            let text = found.and_then(|found| {
+                let text = found.node.value.to_value_kind(span).map(|kind| match kind {
+                    ValueKind::Array(items) => {
+                        items.iter().map(|item| item.kind.to_string()).join(", ")
+                    }
+                    kind => kind.to_string(),
+                });
+                if text.is_none() {
+                    report_not_a_value(&found, span);
+                }
+                text
+            });
+            text.unwrap_or_else(|| captures[0].to_string())
+        });
+
+        Some(filled.into_owned())
+    }
+
+    /// The records a route generates pages for, the items of an array or the rows of a table such
+    /// as a CSV file. Records that aren't tables are reported and left out.
+    pub fn records(&self, path: &str) -> Option<Vec<Found<'_>>> {
+        let path = path.strip_prefix("data.").unwrap_or(path);
+        let found = self.lookup(DATA_SCOPE, path, None, None)?;
+
+        let records = match &found.node.value {
+            DataValue::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, node)| Found { node, path: format!("{}.{i}", found.path), ..found })
+                .collect_vec(),
+            DataValue::Table(entries) => entries
+                .iter()
+                .map(|(key, node)| Found { node, path: format!("{}.{key}", found.path), ..found })
+                .collect_vec(),
+            value => {
+                add_report(
+                    Report::build(ReportKind::Error, found.location())
+                        .with_message(format!("'{}' has no records", found.path))
+                        .with_label(found.label(format!("'{}' is {}", found.path, value.kind())))
+                        .with_note("Routes generate a page for every item of an array or table")
+                        .finish(),
+                );
+                return None;
+            }
+        };
+
+        Some(
+            records
+                .into_iter()
+                .filter(|record| {
+                    let is_table = matches!(record.node.value, DataValue::Table(_));
+                    if !is_table {
+                        add_report(
+                            Report::build(ReportKind::Error, record.location())
+                                .with_message(format!("Record '{}' is not a table", record.path))
+                                .with_label(record.label(format!(
+                                    "'{}' is {}",
+                                    record.path,
+                                    record.node.value.kind()
+                                )))
+                                .with_note("The fields of a record fill in the route's template")
+                                .finish(),
+                        );
+                    }
+                    is_table
+                })
+                .collect(),
+        )
+    }
+
+    /// Follows `path` into the current `item` or the data file it starts with, reporting the first
+    /// part that is missing. `span` is where the reference is written in the current file.
+    fn lookup<'a>(
+        &'a self,
+        scope: &str,
+        path: &str,
+        item: Option<&Found<'a>>,
+        span: Option<&Span>,
+    ) -> Option<Found<'a>> {
+        let keys = path.split('.').collect_vec();
+
+        let (mut found, keys) = if scope == ITEM_SCOPE {
+            let Some(item) = item else {
+                add_report(
+                    reference_report(format!("No item for '{{item.{path}}}'"), span)
+                        .with_note("`item` is the record a route page is generated for, it is only available in route templates")
+                        .finish(),
+                );
+                return None;
+            };
+            (item.clone(), keys.as_slice())
+        } else {
+            // `data/shop.toml` and `data/shop/products.toml` can both exist, the longest name wins
+            let file = self
+                .files
+                .iter()
+                .filter(|file| {
+                    let len = file.name.split('.').count();
+                    keys.len() >= len && keys[..len].join(".") == file.name
+                })
+                .max_by_key(|file| file.name.len());
+            let Some(file) = file else {
+                self.report_unknown_file(path, span);
+                return None;
+            };
+
+            let found = Found { file, node: &file.root, path: format!("data.{}", file.name) };
+            (found, &keys[file.name.split('.').count()..])
+        };
+
+        for key in keys {
+            let next = match &found.node.value {
+                DataValue::Table(entries) => {
+                    entries.iter().find(|(name, _)| name == key).map(|(_, node)| node)
+                }
+                DataValue::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => None,
+            };
+            let Some(next) = next else {
+                report_missing_key(&found, key, span);
+                return None;
+            };
+
+            found.node = next;
+            found.path.push('.');
+            found.path.push_str(key);
+        }
+
+        Some(found)
+    }
+
+    fn report_unknown_file(&self, path: &str, span: Option<&Span>) {
+        let name = path.split('.').next().unwrap_or_default();
+        let note = match did_you_mean(name, self.files.iter().map(|file| file.name.as_str())) {
+            Some(suggestion) => format!("Did you mean 'data.{suggestion}'?"),
+            None if self.files.is_empty() => format!("No data files were found in `{}`", self.dir),
+            None => format!(
+                "Data files in `{}`: {}",
+                self.dir,
+                self.files.iter().map(|file| &file.name).join(", ")
+            ),
+        };
+
+        add_report(
+            reference_report(format!("No data file for 'data.{path}'"), span)
+                .with_note(note)
+                .finish(),
+        );
+    }
+}
+
+/// Report at the reference in the current file. References that aren't written in a page, such
+/// as the data of a route, are reported without a label there.
+fn reference_report(
+    message: String,
+    span: Option<&Span>,
+) -> ReportBuilder<'static, (Arc<MPath>, Span)> {
+    let report =
+        Report::build(ReportKind::Error, (ReportsBag::file(), span.cloned().unwrap_or_default()))
+            .with_message(message);
+
+    match span {
+        Some(span) => report.with_label(
+            Label::new((ReportsBag::file(), span.clone()))
+                .with_message("referenced here")
+                .with_color(Color::BrightRed),
+        ),
+        None => report,
+    }
+}
+
+fn report_missing_key(found: &Found, key: &str, span: Option<&Span>) {
+    let (message, note) = match &found.node.value {
+        DataValue::Table(_) => {
+            let keys = found.node.value.keys();
+            let note = match did_you_mean(key, keys.iter().copied()) {
+                Some(suggestion) => format!("Did you mean '{}.{suggestion}'?", found.path),
+                None if keys.is_empty() => "The table is empty".to_string(),
+                None => format!("Keys of '{}': {}", found.path, keys.join(", ")),
+            };
+            (format!("'{}' has no key '{key}'", found.path), note)
+        }
+        DataValue::Array(items) => (
+            format!("'{}' has no item '{key}'", found.path),
+            match items.len() {
+                0 => "The array is empty".to_string(),
+                len => format!(
+                    "Arrays are indexed from 0, the last item is '{}.{}'",
+                    found.path,
+                    len - 1
+                ),
+            },
+        ),
+        value => (
+            format!("'{}' has no key '{key}'", found.path),
+            format!("'{}' is {}, only tables and arrays have keys", found.path, value.kind()),
+        ),
+    };
+
+    add_report(
+        reference_report(message, span)
+            .with_label(found.label(format!("'{key}' is not declared in here")))
+            .with_note(note)
+            .finish(),
+    );
+}
+
+fn report_not_a_value(found: &Found, span: &Span) {
+    let note = match found.node.value.keys().as_slice() {
+        [] => "Reference one of its items instead".to_string(),
+        keys => format!("Reference one of its keys instead: {}", keys.join(", ")),
+    };
+
+    add_report(
+        reference_report(format!("'{}' can't be used as a value", found.path), Some(span))
+            .with_label(found.label(format!("'{}' is {}", found.path, found.node.value.kind())))
+            .with_note(note)
+            .finish(),
+    );
+}
+
+fn add_report(report: MReport) {
+    ReportsBag::mark_errors();
+    ReportsBag::add(report);
+}
+
+fn parse_toml(content: &str) -> MResult<DataNode> {
+    let doc = toml_edit::ImDocument::parse(content)?;
+    Ok(toml_table(doc.as_table(), 0..0))
+}
+
+fn toml_table(table: &toml_edit::Table, span: Span) -> DataNode {
+    let entries = table
+        .iter()
+        .filter_map(|(key, item)| {
+            let key_span =
+                table.key(key).and_then(toml_edit::Key::span).unwrap_or_else(|| span.clone());
+            Some((key.to_string(), toml_item(item, key_span)?))
+        })
+        .collect();
+
+    DataNode { value: DataValue::Table(entries), span }
+}
+
+fn toml_item(item: &toml_edit::Item, span: Span) -> Option<DataNode> {
+    match item {
+        toml_edit::Item::None => None,
+        toml_edit::Item::Value(value) => Some(toml_value(value, span)),
+        toml_edit::Item::Table(table) => Some(toml_table(table, span)),
+        toml_edit::Item::ArrayOfTables(tables) => {
+            let items = tables
+                .iter()
+                .map(|table| toml_table(table, table.span().unwrap_or_else(|| span.clone())))
+                .collect();
+            Some(DataNode { value: DataValue::Array(items), span })
+        }
+    }
+}
+
+fn toml_value(value: &toml_edit::Value, span: Span) -> DataNode {
+    let value = match value {
+        toml_edit::Value::String(s) => DataValue::String(s.value().clone()),
+        #[expect(clippy::cast_precision_loss)]
+        toml_edit::Value::Integer(i) => DataValue::Number(*i.value() as f64),
+        toml_edit::Value::Float(f) => DataValue::Number(*f.value()),
+        toml_edit::Value::Boolean(b) => DataValue::Boolean(*b.value()),
+        toml_edit::Value::Datetime(date) => DataValue::String(date.value().to_string()),
+        toml_edit::Value::Array(items) => DataValue::Array(
+            items
+                .iter()
+                .map(|item| toml_value(item, item.span().unwrap_or_else(|| span.clone())))
+                .collect(),
+        ),
+        toml_edit::Value::InlineTable(table) => DataValue::Table(
+            table
+                .iter()
+                .map(|(key, item)| {
+                    let key_span = table
+                        .key(key)
+                        .and_then(toml_edit::Key::span)
+                        .unwrap_or_else(|| span.clone());
+                    (key.to_string(), toml_value(item, key_span))
+                })
+                .collect(),
+        ),
+    };
+
+    DataNode { value, span }
+}
+
+fn parse_json(content: &str) -> MResult<DataNode> {
+    // serde_json reports the syntax errors, the source is walked again to keep the spans of keys
+    serde_json::from_str::<serde_json::Value>(content)?;
+
+    let mut source = JsonSource { content, pos: 0 };
+    source.skip_whitespace();
+    Ok(source.node(0..0))
+}
+
+/// Walks JSON that serde_json has already accepted and keeps where every key is declared.
+struct JsonSource<'a> {
+    content: &'a str,
+    pos: usize,
+}
+
+impl JsonSource<'_> {
+    /// The value at the current position, declared at `span`. Items of arrays are declared at
+    /// their value.
+    fn node(&mut self, span: Span) -> DataNode {
+        let value = match self.content.as_bytes()[self.pos] {
+            b'{' => {
+                let mut entries: Vec<(String, DataNode)> = vec![];
+                while self.next_item(b'}') {
+                    let start = self.pos;
+                    let key = self.string();
+                    let key_span = start..self.pos;
+                    self.skip_whitespace();
+                    self.pos += 1; // ':'
+                    self.skip_whitespace();
+
+                    let node = self.node(key_span);
+                    // the last of duplicate keys wins, as in serde_json
+                    entries.retain(|(name, _)| *name != key);
+                    entries.push((key, node));
+                }
+                DataValue::Table(entries)
+            }
+            b'[' => {
+                let mut items = vec![];
+                while self.next_item(b']') {
+                    let start = self.pos;
+                    let mut node = self.node(span.clone());
+                    node.span = start..self.pos;
+                    items.push(node);
+                }
+                DataValue::Array(items)
+            }
+            b'"' => DataValue::String(self.string()),
+            b't' => self.literal("true", DataValue::Boolean(true)),
+            b'f' => self.literal("false", DataValue::Boolean(false)),
+            b'n' => self.literal("null", DataValue::String(String::new())),
+            _ => {
+                let rest = &self.content[self.pos..];
+                let len = rest
+                    .find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                    .unwrap_or(rest.len());
+                self.pos += len;
+                DataValue::Number(rest[..len].parse().unwrap_or_default())
+            }
+        };
+
+        DataNode { value, span }
+    }
+
+    /// Moves to the next item of an object or array, past its opening bracket or the comma
+    /// before it. `false` once past `close`.
+    fn next_item(&mut self, close: u8) -> bool {
+        self.skip_whitespace();
+        if self.content.as_bytes()[self.pos] != close {
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+
+        let done = self.content.as_bytes()[self.pos] == close;
+        if done {
+            self.pos += 1;
+        }
+        !done
+    }
+
+    fn string(&mut self) -> String {
+        let bytes = self.content.as_bytes();
+        let start = self.pos;
+        let mut end = start + 1;
+        while bytes[end] != b'"' {
+            end += if bytes[end] == b'\\' { 2 } else { 1 };
+        }
+        self.pos = end + 1;
+
+        serde_json::from_str(&self.content[start..self.pos]).unwrap_or_default()
+    }
+
+    fn literal(&mut self, literal: &str, value: DataValue) -> DataValue {
+        self.pos += literal.len();
+        value
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.content[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+}
+
+fn parse_csv(content: &str) -> MResult<DataNode> {
+    let mut records = csv_records(content)?.into_iter();
+    let header = records.next().unwrap_or_default();
+
+    let mut rows: Vec<(String, DataNode)> = vec![];
+    for record in records {
+        let (key, span) = record[0].clone();
+        if record.len() != header.len() {
+            return Err(anyhow!(
+                "row '{key}' has {} columns, the header has {}",
+                record.len(),
+                header.len()
+            ));
+        }
+        if rows.iter().any(|(name, _)| *name == key) {
+            return Err(anyhow!("more than one row starts with '{key}'"));
+        }
+
+        let cells = header
+            .iter()
+            .zip(record)
+            .map(|((name, _), (cell, span))| {
+                (name.clone(), DataNode { value: csv_cell(cell), span })
+            })
+            .collect();
+        rows.push((key, DataNode { value: DataValue::Table(cells), span }));
+    }
+
+    Ok(DataNode { value: DataValue::Table(rows), span: 0..0 })
+}
+
+fn csv_cell(cell: String) -> DataValue {
+    match cell.trim() {
+        "true" => DataValue::Boolean(true),
+        "false" => DataValue::Boolean(false),
+        trimmed => match trimmed.parse::<f64>() {
+            Ok(n) if !trimmed.is_empty() => DataValue::Number(n),
+            _ => DataValue::String(cell),
+        },
+    }
+}
+
+/// Splits CSV into records of fields and their spans, skipping lines without any content.
+///
+/// See https://www.rfc-editor.org/rfc/rfc4180
+fn csv_records(content: &str) -> MResult<Vec<Vec<(String, Span)>>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut start = 0;
+    let mut quoted = false;
+
+    let mut chars = content.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.next_if(|(_, c)| *c == '"').is_some() {
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if i == start => quoted = true,
+            ',' if !quoted => {
+                record.push((std::mem::take(&mut field), start..i));
+                start = chars.peek().map_or(content.len(), |(i, _)| *i);
+            }
+            '\n' | '\r' if !quoted => {
+                chars.next_if(|(_, next)| c == '\r' && *next == '\n');
+                record.push((std::mem::take(&mut field), start..i));
+                start = chars.peek().map_or(content.len(), |(i, _)| *i);
+
+                if record.iter().all(|(field, _)| field.is_empty()) {
+                    record.clear();
+                } else {
+                    records.push(std::mem::take(&mut record));
+                }
+            }
+            c => field.push(c),
+        }
+    }
+
+    if quoted {
+        let line = content[..start].matches('\n').count() + 1;
+        return Err(anyhow!("the quoted field on line {line} is never closed"));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push((field, start..content.len()));
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(content: &str) -> Vec<Vec<String>> {
+        csv_records(content)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.into_iter().map(|(field, _)| field).collect())
+            .collect()
+    }
+
+    fn table(node: &DataNode) -> &[(String, DataNode)] {
+        match &node.value {
+            DataValue::Table(entries) => entries,
+            value => panic!("expected a table, found {}", value.kind()),
+        }
+    }
+
+    fn entry<'a>(node: &'a DataNode, key: &str) -> &'a DataNode {
+        &table(node).iter().find(|(name, _)| name == key).unwrap().1
+    }
+
+    #[test]
+    fn splits_csv_records() {
+        assert_eq!(fields("id,name\na,Jane\n"), [["id", "name"], ["a", "Jane"]]);
+        assert_eq!(fields("id,name\r\na,Jane\r\n"), [["id", "name"], ["a", "Jane"]]);
+        assert_eq!(fields("id,name\na,Jane"), [["id", "name"], ["a", "Jane"]]);
+        assert_eq!(fields("id,name\n\n\r\na,\n"), [["id", "name"], ["a", ""]]);
+        assert_eq!(fields(""), Vec::<Vec<String>>::new());
+    }
+
+    #[test]
+    fn unquotes_csv_fields() {
+        assert_eq!(fields(r#""a, b","say ""hi""","""#), [["a, b", r#"say "hi""#, ""]]);
+        assert_eq!(fields("\"two\r\nlines\",x\n"), [["two\r\nlines", "x"]]);
+        // quotes only start a quoted field at its beginning
+        assert_eq!(fields(r#"5" screen,x"#), [[r#"5" screen"#, "x"]]);
+        assert!(csv_records("id\n\"open\n").is_err());
+    }
+
+    #[test]
+    fn keeps_spans_of_csv_fields() {
+        let content = "id,name\r\n\"a\",Jane";
+        let records = csv_records(content).unwrap();
+
+        assert_eq!(records[0][1].1, 3..7);
+        assert_eq!(&content[records[1][0].1.clone()], "\"a\"");
+        assert_eq!(&content[records[1][1].1.clone()], "Jane");
+    }
+
+    #[test]
+    fn keys_csv_rows_by_their_first_column() {
+        let root = parse_csv("id,age,admin\njane,41,true\njoe,,false\n").unwrap();
+
+        assert_eq!(table(&root).iter().map(|(key, _)| key.as_str()).collect_vec(), ["jane", "joe"]);
+        assert!(matches!(entry(entry(&root, "jane"), "age").value, DataValue::Number(41.0)));
+        assert!(matches!(entry(entry(&root, "joe"), "admin").value, DataValue::Boolean(false)));
+        assert!(
+            matches!(&entry(entry(&root, "joe"), "age").value, DataValue::String(s) if s.is_empty())
+        );
+
+        assert!(parse_csv("id,age\njane\n").is_err());
+        assert!(parse_csv("id,age\njane,41\njane,42\n").is_err());
+    }
+
+    #[test]
+    fn keeps_spans_of_json_keys() {
+        let content =
+            r#"{"b": {"name": "x"}, "a": [{"name": "y"}, 2, [true]], "a": [null, {"name": "z"}]}"#;
+        let root = parse_json(content).unwrap();
+
+        // keys keep their order, the last of duplicate keys wins
+        assert_eq!(table(&root).iter().map(|(key, _)| key.as_str()).collect_vec(), ["b", "a"]);
+
+        let b = entry(&root, "b");
+        assert_eq!(&content[b.span.clone()], r#""b""#);
+        assert_eq!(entry(b, "name").span, content.find(r#""name""#).unwrap()..13);
+
+        let DataValue::Array(items) = &entry(&root, "a").value else { panic!("expected an array") };
+        assert_eq!(&content[items[0].span.clone()], "null");
+        assert!(matches!(&items[0].value, DataValue::String(s) if s.is_empty()));
+        assert_eq!(&content[items[1].span.clone()], r#"{"name": "z"}"#);
+        assert_eq!(entry(&items[1], "name").span.start, content.rfind(r#""name""#).unwrap());
+    }
+
+    #[test]
+    fn reads_json_values() {
+        let root = parse_json(" {\"s\": \"a\\\"b\\u00e9\", \"n\": -1.5e2, \"t\": true}\n").unwrap();
+
+        assert!(matches!(&entry(&root, "s").value, DataValue::String(s) if s == "a\"bé"));
+        assert!(matches!(entry(&root, "n").value, DataValue::Number(-150.0)));
+        assert!(matches!(entry(&root, "t").value, DataValue::Boolean(true)));
+        assert!(parse_json("{\"a\": }").is_err());
+    }
+}
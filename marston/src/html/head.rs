@@ -0,0 +1,58 @@
+//! `.head` attributes that are rendered as tags inside `<head>`.
+//!
+//! The built-in mappings can be extended with `[[head]]` entries in `marston.toml`. Attributes
+//! without a mapping have no HTML equivalent and are reported by the validator.
+
+/// Tag an attribute is rendered as. The attribute value becomes the `content` of meta tags and
+/// the `href` of links.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeadTarget {
+    /// `<meta name="..." content="...">`
+    Meta(String),
+    /// `<meta property="..." content="...">`
+    Property(String),
+    /// `<meta http-equiv="..." content="...">`
+    HttpEquiv(String),
+    /// `<link rel="..." href="...">`
+    Link(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct HeadMapping {
+    pub attribute: String,
+    pub target: HeadTarget,
+}
+
+impl HeadMapping {
+    pub fn new(attribute: &str, target: HeadTarget) -> Self {
+        Self { attribute: attribute.to_string(), target }
+    }
+}
+
+/// Attributes of `.head` that are handled on their own instead of through a mapping. `date` is
+/// only read by feeds.
+pub const SPECIAL_HEAD_ATTRIBUTES: &[&str] = &["lang", "title", "charset", "date"];
+
+pub fn builtin_head_mappings() -> Vec<HeadMapping> {
+    let meta = |attribute, name: &str| HeadMapping::new(attribute, HeadTarget::Meta(name.into()));
+    let http_equiv =
+        |attribute, name: &str| HeadMapping::new(attribute, HeadTarget::HttpEquiv(name.into()));
+
+    vec![
+        meta("viewport", "viewport"),
+        meta("description", "description"),
+        meta("keywords", "keywords"),
+        meta("author", "author"),
+        meta("robots", "robots"),
+        meta("generator", "generator"),
+        meta("referrer", "referrer"),
+        meta("theme_color", "theme-color"),
+        meta("color_scheme", "color-scheme"),
+        meta("application_name", "application-name"),
+        HeadMapping::new("canonical", HeadTarget::Link("canonical".into())),
+        http_equiv("content_security_policy", "Content-Security-Policy"),
+        http_equiv("refresh", "refresh"),
+        http_equiv("default_style", "default-style"),
+        http_equiv("x_ua_compatible", "X-UA-Compatible"),
+    ]
+}
@@ -1,22 +1,42 @@
-use crate::ast::{
-    Attribute, Block, MarstonDocument, Node, ValueKind,
-    ident_table::{get_or_intern, resolve},
+use crate::{
+    ast::{
+        Attribute, Block, MarstonDocument, Node, ValueKind,
+        ident_table::{get_or_intern, resolve},
+    },
+    html::{
+        head::{HeadMapping, HeadTarget},
+        social::{OPEN_GRAPH, SocialVocabulary, TWITTER},
+        structured_data::{JSON_LD_BLOCK, JSON_LD_MIME, to_json_ld},
+    },
+    site::sitemap::SITEMAP_BLOCK,
 };
 use itertools::Itertools;
 use lasso::Spur;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IrDoc {
     pub root: Vec<IrNode>,
 }
 
-#[derive(Debug)]
+impl IrDoc {
+    pub fn apply(&mut self, transformation: &dyn IrTransformation) {
+        for node in &mut self.root {
+            if let IrNode::Element(element) = node {
+                transformation.apply(element);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum IrNode {
     Element(IrElement),
     Text(String),
+    /// Written as is, for script content that must not be HTML escaped
+    Raw(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IrElement {
     pub tag: Spur,
     pub attributes: Vec<IrAttribute>,
@@ -33,7 +53,7 @@ impl IrElement {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IrAttribute {
     pub key: Spur,
     pub value: ValueKind,
@@ -111,11 +131,105 @@ impl IrTransformBuilder {
         self.add_transformation(Box::new(AttributeToMetaTransform {
             source_tag: get_or_intern(source_tag),
             attr_key: get_or_intern(attr_key),
+            target_tag: get_or_intern("meta"),
+            name_key: get_or_intern("name"),
             meta_name: get_or_intern(meta_name),
+            value_key: get_or_intern("content"),
             value_mapper,
         }))
     }
 
+    /// Renders the attribute as the tag `mapping` points to, joining array values with ", ".
+    #[must_use]
+    pub fn head_mapping(self, source_tag: &str, mapping: &HeadMapping) -> Self {
+        let (target_tag, name_key, name, value_key) = match &mapping.target {
+            HeadTarget::Meta(name) => ("meta", "name", name, "content"),
+            HeadTarget::Property(name) => ("meta", "property", name, "content"),
+            HeadTarget::HttpEquiv(name) => ("meta", "http-equiv", name, "content"),
+            HeadTarget::Link(rel) => ("link", "rel", rel, "href"),
+        };
+
+        self.add_transformation(Box::new(AttributeToMetaTransform {
+            source_tag: get_or_intern(source_tag),
+            attr_key: get_or_intern(&mapping.attribute),
+            target_tag: get_or_intern(target_tag),
+            name_key: get_or_intern(name_key),
+            meta_name: get_or_intern(name),
+            value_key: get_or_intern(value_key),
+            value_mapper: Some(Box::new(join_array)),
+        }))
+    }
+
+    /// Like [`Self::attribute_to_meta_tag`], but names the meta tag with `property` as Open Graph
+    /// expects.
+    #[must_use]
+    pub fn attribute_to_meta_property(
+        self,
+        source_tag: &str,
+        attr_key: &str,
+        property: &str,
+    ) -> Self {
+        self.add_transformation(Box::new(AttributeToMetaTransform {
+            source_tag: get_or_intern(source_tag),
+            attr_key: get_or_intern(attr_key),
+            target_tag: get_or_intern("meta"),
+            name_key: get_or_intern("property"),
+            meta_name: get_or_intern(property),
+            value_key: get_or_intern("content"),
+            value_mapper: None,
+        }))
+    }
+
+    /// Moves the attributes of `child_tag` elements to their `parent_tag` parent, prefixing their
+    /// names, and removes the child elements.
+    #[must_use]
+    pub fn flatten_block(self, parent_tag: &str, child_tag: &str, prefix: &str) -> Self {
+        self.add_transformation(Box::new(FlattenBlockTransform {
+            parent_tag: get_or_intern(parent_tag),
+            child_tag: get_or_intern(child_tag),
+            prefix: prefix.to_string(),
+        }))
+    }
+
+    /// Sets the attribute to the value computed by `default` if the element doesn't have it.
+    #[must_use]
+    pub fn default_attribute<F>(self, tag: &str, attr_key: &str, default: F) -> Self
+    where
+        F: Fn(&IrElement) -> Option<ValueKind> + 'static,
+    {
+        self.add_transformation(Box::new(DefaultAttributeTransform {
+            tag: get_or_intern(tag),
+            attr_key: get_or_intern(attr_key),
+            default: Box::new(default),
+        }))
+    }
+
+    /// Replaces `tag` elements with a JSON-LD script holding their structured data.
+    #[must_use]
+    pub fn block_to_json_ld(self, tag: &str) -> Self {
+        self.add_transformation(Box::new(JsonLdTransform { tag: get_or_intern(tag) }))
+    }
+
+    /// Removes `tag` elements that are direct children of `parent_tag` elements.
+    #[must_use]
+    pub fn remove_element(self, parent_tag: &str, tag: &str) -> Self {
+        self.add_transformation(Box::new(RemoveElementTransform {
+            parent_tag: get_or_intern(parent_tag),
+            tag: get_or_intern(tag),
+        }))
+    }
+
+    /// Renames `tag` attributes starting with `from` to start with `to` instead, e.g. `data_theme`
+    /// to `data-theme`.
+    #[must_use]
+    pub fn rename_attribute_prefix(self, tag: &str, from: &str, to: &str) -> Self {
+        self.add_transformation(Box::new(RenameAttributePrefixTransform {
+            tag: get_or_intern(tag),
+            from: from.to_string(),
+            to: to.to_string(),
+        }))
+    }
+
     pub fn apply(&self, element: &mut IrElement) {
         for transformation in &self.transformations {
             transformation.apply(element);
@@ -284,10 +398,17 @@ impl AttributeToElementTransform {
     }
 }
 
+/// Renders an attribute as a `meta` tag, or any tag that names itself with one attribute and holds
+/// the value in another, such as `<link rel="..." href="...">`.
 pub struct AttributeToMetaTransform {
     source_tag: Spur,
     attr_key: Spur,
+    target_tag: Spur,
+    /// `name`, `property`, `http-equiv` or `rel`
+    name_key: Spur,
     meta_name: Spur,
+    /// `content` or `href`
+    value_key: Spur,
     value_mapper: Option<Box<dyn Fn(&ValueKind) -> ValueKind>>,
 }
 
@@ -301,9 +422,9 @@ impl AttributeToMetaTransform {
     fn apply_recursive(&self, element: &mut IrElement) {
         if element.tag == self.source_tag {
             if let Some(attr) = element.get_attribute(self.attr_key) {
-                let mut new_element = IrElement::new(get_or_intern("meta"));
+                let mut new_element = IrElement::new(self.target_tag);
                 new_element.attributes.push(IrAttribute {
-                    key: get_or_intern("name"),
+                    key: self.name_key,
                     value: ValueKind::String(resolve(self.meta_name).to_string()),
                 });
                 let value = if let Some(mapper) = &self.value_mapper {
@@ -312,7 +433,7 @@ impl AttributeToMetaTransform {
                     attr.value.clone()
                 };
 
-                new_element.attributes.push(IrAttribute { key: get_or_intern("content"), value });
+                new_element.attributes.push(IrAttribute { key: self.value_key, value });
                 element.children.push(IrNode::Element(new_element));
             }
         }
@@ -350,6 +471,165 @@ impl RemoveAttributeTransform {
     }
 }
 
+pub struct RemoveElementTransform {
+    parent_tag: Spur,
+    tag: Spur,
+}
+
+impl IrTransformation for RemoveElementTransform {
+    fn apply(&self, element: &mut IrElement) {
+        self.apply_recursive(element);
+    }
+}
+
+impl RemoveElementTransform {
+    fn apply_recursive(&self, element: &mut IrElement) {
+        if element.tag == self.parent_tag {
+            element
+                .children
+                .retain(|child| !matches!(child, IrNode::Element(child) if child.tag == self.tag));
+        }
+
+        for child in &mut element.children {
+            if let IrNode::Element(child_element) = child {
+                self.apply_recursive(child_element);
+            }
+        }
+    }
+}
+
+pub struct RenameAttributePrefixTransform {
+    tag: Spur,
+    from: String,
+    to: String,
+}
+
+impl IrTransformation for RenameAttributePrefixTransform {
+    fn apply(&self, element: &mut IrElement) {
+        self.apply_recursive(element);
+    }
+}
+
+impl RenameAttributePrefixTransform {
+    fn apply_recursive(&self, element: &mut IrElement) {
+        if element.tag == self.tag {
+            for attr in &mut element.attributes {
+                if let Some(rest) = resolve(attr.key).strip_prefix(&self.from) {
+                    attr.key = get_or_intern(&format!("{}{rest}", self.to));
+                }
+            }
+        }
+
+        for child in &mut element.children {
+            if let IrNode::Element(child_element) = child {
+                self.apply_recursive(child_element);
+            }
+        }
+    }
+}
+
+pub struct FlattenBlockTransform {
+    parent_tag: Spur,
+    child_tag: Spur,
+    prefix: String,
+}
+
+impl IrTransformation for FlattenBlockTransform {
+    fn apply(&self, element: &mut IrElement) {
+        self.apply_recursive(element);
+    }
+}
+
+impl FlattenBlockTransform {
+    fn apply_recursive(&self, element: &mut IrElement) {
+        if element.tag == self.parent_tag {
+            let mut flattened = vec![];
+
+            element.children.retain_mut(|child| match child {
+                IrNode::Element(child) if child.tag == self.child_tag => {
+                    flattened.append(&mut child.attributes);
+                    false
+                }
+                _ => true,
+            });
+
+            element.attributes.extend(flattened.into_iter().map(|attr| IrAttribute {
+                key: get_or_intern(&format!("{}{}", self.prefix, resolve(attr.key))),
+                value: attr.value,
+            }));
+        }
+
+        for child in &mut element.children {
+            if let IrNode::Element(child_element) = child {
+                self.apply_recursive(child_element);
+            }
+        }
+    }
+}
+
+pub struct JsonLdTransform {
+    tag: Spur,
+}
+
+impl IrTransformation for JsonLdTransform {
+    fn apply(&self, element: &mut IrElement) {
+        self.apply_recursive(element);
+    }
+}
+
+impl JsonLdTransform {
+    fn apply_recursive(&self, element: &mut IrElement) {
+        if element.tag == self.tag {
+            let json = to_json_ld(element);
+
+            element.tag = get_or_intern("script");
+            element.attributes = vec![IrAttribute {
+                key: get_or_intern("type"),
+                value: ValueKind::String(JSON_LD_MIME.to_string()),
+            }];
+            element.children = vec![IrNode::Raw(json)];
+            return;
+        }
+
+        for child in &mut element.children {
+            if let IrNode::Element(child_element) = child {
+                self.apply_recursive(child_element);
+            }
+        }
+    }
+}
+
+type DefaultValue = Box<dyn Fn(&IrElement) -> Option<ValueKind>>;
+
+pub struct DefaultAttributeTransform {
+    tag: Spur,
+    attr_key: Spur,
+    default: DefaultValue,
+}
+
+impl IrTransformation for DefaultAttributeTransform {
+    fn apply(&self, element: &mut IrElement) {
+        self.apply_recursive(element);
+    }
+}
+
+impl DefaultAttributeTransform {
+    fn apply_recursive(&self, element: &mut IrElement) {
+        if element.tag == self.tag
+            && element.get_attribute(self.attr_key).is_none()
+            && let Some(value) = (self.default)(element)
+        {
+            element.attributes.push(IrAttribute { key: self.attr_key, value });
+        }
+
+        for child in &mut element.children {
+            if let IrNode::Element(child_element) = child {
+                self.apply_recursive(child_element);
+            }
+        }
+    }
+}
+
 fn find_element(elements: &mut [IrNode], tag: Spur) -> &mut IrElement {
     elements
         .iter_mut()
@@ -361,16 +641,32 @@ fn find_element(elements: &mut [IrNode], tag: Spur) -> &mut IrElement {
 }
 
 pub trait ToHtmlIR {
-    fn to_html_ir(&self) -> IrDoc;
+    /// `head` lists the `.head` attributes that are rendered as tags, see
+    /// [`Config::head_mappings`](crate::config::Config::head_mappings).
+    fn to_html_ir(&self, head: &[HeadMapping]) -> IrDoc;
 }
 
 impl ToHtmlIR for MarstonDocument {
-    fn to_html_ir(&self) -> IrDoc {
-        let mut root = IrElement::new(get_or_intern("html"));
-        let elements = self.blocks.iter().map(|b| IrNode::Element(b.to_element())).collect();
-        root.children = elements;
-
-        let transformer = IrTransformBuilder::new()
+    fn to_html_ir(&self, head: &[HeadMapping]) -> IrDoc {
+        // an `.html` block wrapping head and body provides the root with its attributes
+        let mut root = if let Some(html) = self.html_block() {
+            html.to_element()
+        } else {
+            let mut root = IrElement::new(get_or_intern("html"));
+            // `.page` only holds build metadata
+            root.children = self
+                .blocks
+                .iter()
+                .filter(|b| !b.is_front_matter())
+                .map(|b| IrNode::Element(b.to_element()))
+                .collect();
+            root
+        };
+
+        let transformer = social_metadata_defaults(IrTransformBuilder::new())
+            .rename_attribute_prefix("html", "data_", "data-")
+            // only read when the sitemap is generated
+            .remove_element("head", SITEMAP_BLOCK)
             .move_attribute_to_root("head", "lang")
             .attribute_to_tag("head", "title", "title", |value| {
                 Some(vec![IrNode::Text(value.as_string().unwrap_or(&"".to_string()).clone())])
@@ -378,27 +674,13 @@ impl ToHtmlIR for MarstonDocument {
             .remove_attribute("head", "title")
             .attribute_to_element("head", "charset", "meta")
             .remove_attribute("head", "charset")
-            .attribute_to_meta_tag("head", "viewport", "viewport", None)
-            .remove_attribute("head", "viewport")
-            .attribute_to_meta_tag("head", "description", "description", None)
-            .remove_attribute("head", "description")
-            .attribute_to_meta_tag(
-                "head",
-                "keywords",
-                "keywords",
-                Some(Box::new(|value| {
-                    if let Some(value) = value.as_array() {
-                        return ValueKind::String(
-                            value.iter().map(|v| v.kind.as_string().unwrap()).join(", "),
-                        );
-                    } else {
-                        panic!("keywords must be an array. should be checked in the validators");
-                    }
-                })),
-            )
-            .remove_attribute("head", "keywords")
-            .attribute_to_meta_tag("head", "author", "author", None)
-            .remove_attribute("head", "author");
+            .remove_attribute("head", "date");
+        let transformer = head.iter().fold(transformer, |transformer, mapping| {
+            transformer.head_mapping("head", mapping).remove_attribute("head", &mapping.attribute)
+        });
+        let transformer = social_meta_tags(transformer, &OPEN_GRAPH, true);
+        let transformer = social_meta_tags(transformer, &TWITTER, false);
+        let transformer = transformer.block_to_json_ld(JSON_LD_BLOCK);
 
         transformer.apply(&mut root);
 
@@ -406,6 +688,72 @@ impl ToHtmlIR for MarstonDocument {
     }
 }
 
+/// Lists such as `keywords` are written comma separated.
+fn join_array(value: &ValueKind) -> ValueKind {
+    match value.as_array() {
+        Some(values) => ValueKind::String(values.iter().map(|v| v.kind.to_string()).join(", ")),
+        None => value.clone(),
+    }
+}
+
+fn has_prefixed_attribute(element: &IrElement, prefix: &str) -> bool {
+    element.attributes.iter().any(|attr| resolve(attr.key).starts_with(prefix))
+}
+
+/// Flattens the `.og` and `.twitter` blocks and fills in what can be derived from the rest of the
+/// head, so pages only have to add what differs. Has to run before `title` and `description` are
+/// turned into tags.
+fn social_metadata_defaults(builder: IrTransformBuilder) -> IrTransformBuilder {
+    let copy_of = |attr: &'static str| {
+        move |head: &IrElement| {
+            has_prefixed_attribute(head, OPEN_GRAPH.prefix)
+                .then(|| head.get_attribute(get_or_intern(attr)).map(|a| a.value.clone()))
+                .flatten()
+        }
+    };
+
+    builder
+        .flatten_block("head", OPEN_GRAPH.block, OPEN_GRAPH.prefix)
+        .flatten_block("head", TWITTER.block, TWITTER.prefix)
+        .default_attribute("head", "og_title", copy_of("title"))
+        .default_attribute("head", "og_description", copy_of("description"))
+        .default_attribute("head", "og_type", |head| {
+            has_prefixed_attribute(head, OPEN_GRAPH.prefix)
+                .then(|| ValueKind::String("website".to_string()))
+        })
+        .default_attribute("head", "twitter_card", |head| {
+            if !has_prefixed_attribute(head, OPEN_GRAPH.prefix)
+                && !has_prefixed_attribute(head, TWITTER.prefix)
+            {
+                return None;
+            }
+
+            let has_image = ["og_image", "twitter_image"]
+                .iter()
+                .any(|attr| head.get_attribute(get_or_intern(attr)).is_some());
+            let card = if has_image { "summary_large_image" } else { "summary" };
+            Some(ValueKind::String(card.to_string()))
+        })
+}
+
+/// Open Graph names its meta tags with `property`, Twitter with `name`.
+fn social_meta_tags(
+    builder: IrTransformBuilder,
+    vocabulary: &SocialVocabulary,
+    as_property: bool,
+) -> IrTransformBuilder {
+    vocabulary.properties.iter().fold(builder, |builder, (name, property)| {
+        let attr = format!("{}{name}", vocabulary.prefix);
+        let builder = if as_property {
+            builder.attribute_to_meta_property("head", &attr, property)
+        } else {
+            builder.attribute_to_meta_tag("head", &attr, property, None)
+        };
+
+        builder.remove_attribute("head", &attr)
+    })
+}
+
 impl Block {
     fn to_element(&self) -> IrElement {
         IrElement {
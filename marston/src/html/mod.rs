@@ -1,3 +1,6 @@
+pub mod head;
 pub mod ir;
 pub mod lang;
+pub mod social;
+pub mod structured_data;
 pub mod tags;
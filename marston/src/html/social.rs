@@ -0,0 +1,90 @@
+//! Open Graph and Twitter Card metadata.
+//!
+//! Both can be written as `.head` attributes with a prefix, e.g. `.og_title`, or as attributes of
+//! a nested `.og { ... }` / `.twitter { ... }` block. Names can't contain ':', so structured
+//! properties such as `og:image:alt` are written `image_alt`.
+
+/// Prefix of the `.head` attributes, name of the nested block and the properties it supports.
+pub struct SocialVocabulary {
+    /// Name used in reports
+    pub label: &'static str,
+    pub prefix: &'static str,
+    pub block: &'static str,
+    /// Attribute name and the property it is rendered as
+    pub properties: &'static [(&'static str, &'static str)],
+}
+
+impl SocialVocabulary {
+    pub fn property(&self, attr: &str) -> Option<&'static str> {
+        self.properties.iter().find(|(name, _)| *name == attr).map(|(_, property)| *property)
+    }
+
+    /// Attribute names without the prefix.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> {
+        self.properties.iter().map(|(name, _)| *name)
+    }
+}
+
+/// See https://ogp.me
+pub const OPEN_GRAPH: SocialVocabulary = SocialVocabulary {
+    label: "Open Graph",
+    prefix: "og_",
+    block: "og",
+    properties: &[
+        ("title", "og:title"),
+        ("type", "og:type"),
+        ("url", "og:url"),
+        ("image", "og:image"),
+        ("image_alt", "og:image:alt"),
+        ("image_width", "og:image:width"),
+        ("image_height", "og:image:height"),
+        ("image_type", "og:image:type"),
+        ("image_secure_url", "og:image:secure_url"),
+        ("description", "og:description"),
+        ("site_name", "og:site_name"),
+        ("locale", "og:locale"),
+        ("locale_alternate", "og:locale:alternate"),
+        ("determiner", "og:determiner"),
+        ("audio", "og:audio"),
+        ("video", "og:video"),
+    ],
+};
+
+/// See https://developer.x.com/en/docs/x-for-websites/cards/overview/markup
+pub const TWITTER: SocialVocabulary = SocialVocabulary {
+    label: "Twitter Card",
+    prefix: "twitter_",
+    block: "twitter",
+    properties: &[
+        ("card", "twitter:card"),
+        ("site", "twitter:site"),
+        ("site_id", "twitter:site:id"),
+        ("creator", "twitter:creator"),
+        ("creator_id", "twitter:creator:id"),
+        ("title", "twitter:title"),
+        ("description", "twitter:description"),
+        ("image", "twitter:image"),
+        ("image_alt", "twitter:image:alt"),
+        ("player", "twitter:player"),
+        ("player_width", "twitter:player:width"),
+        ("player_height", "twitter:player:height"),
+        ("player_stream", "twitter:player:stream"),
+    ],
+};
+
+pub const OPEN_GRAPH_TYPES: &[&str] = &[
+    "website",
+    "article",
+    "book",
+    "profile",
+    "music.song",
+    "music.album",
+    "music.playlist",
+    "music.radio_station",
+    "video.movie",
+    "video.episode",
+    "video.tv_show",
+    "video.other",
+];
+
+pub const TWITTER_CARDS: &[&str] = &["summary", "summary_large_image", "app", "player"];
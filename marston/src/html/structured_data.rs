@@ -0,0 +1,160 @@
+//! schema.org structured data written as `.json_ld` blocks.
+//!
+//! Attributes become properties and child blocks become nested objects, or an array when a block
+//! name is repeated. Names are written in snake case and converted to the camel case schema.org
+//! uses, `context`, `type` and `id` become the JSON-LD keywords `@context`, `@type` and `@id`.
+//! The context defaults to schema.org.
+
+use crate::{
+    ast::{ValueKind, ident_table::resolve},
+    html::ir::{IrElement, IrNode},
+};
+use serde_json::{Map, Value as Json};
+
+pub const JSON_LD_BLOCK: &str = "json_ld";
+pub const JSON_LD_MIME: &str = "application/ld+json";
+
+/// Converts a block name to the property it stands for.
+pub fn property_name(name: &str) -> String {
+    match name {
+        "context" => "@context".to_string(),
+        "type" => "@type".to_string(),
+        "id" => "@id".to_string(),
+        _ => {
+            let mut property = String::with_capacity(name.len());
+            let mut upper = false;
+
+            for c in name.chars() {
+                if c == '_' {
+                    upper = !property.is_empty();
+                } else if upper {
+                    property.push(c.to_ascii_uppercase());
+                    upper = false;
+                } else {
+                    property.push(c);
+                }
+            }
+
+            property
+        }
+    }
+}
+
+/// Serializes a `.json_ld` element into the content of its script tag.
+pub fn to_json_ld(element: &IrElement) -> String {
+    let mut object = Map::new();
+    object.insert("@context".to_string(), Json::String("https://schema.org".to_string()));
+    object.extend(to_object(element));
+
+    // `</script>` in a string would end the script element early
+    Json::Object(object).to_string().replace("</", "<\\/")
+}
+
+fn to_object(element: &IrElement) -> Map<String, Json> {
+    let mut object = Map::new();
+
+    for attr in &element.attributes {
+        object.insert(property_name(&resolve(attr.key)), to_json(&attr.value));
+    }
+
+    for child in &element.children {
+        let IrNode::Element(child) = child else {
+            continue;
+        };
+        let value = Json::Object(to_object(child));
+
+        match object.get_mut(&property_name(&resolve(child.tag))) {
+            Some(Json::Array(values)) => values.push(value),
+            Some(existing) => *existing = Json::Array(vec![existing.take(), value]),
+            None => {
+                object.insert(property_name(&resolve(child.tag)), value);
+            }
+        }
+    }
+
+    object
+}
+
+fn to_json(value: &ValueKind) -> Json {
+    match value {
+        ValueKind::String(s) => Json::String(s.clone()),
+        ValueKind::Boolean(b) => Json::Bool(*b),
+        // positions and counts are written without a fraction
+        #[expect(clippy::cast_possible_truncation)]
+        ValueKind::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => Json::from(*n as i64),
+        ValueKind::Number(n) => Json::from(*n),
+        ValueKind::Array(values) => Json::Array(values.iter().map(|v| to_json(&v.kind)).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::ident_table::get_or_intern, html::ir::IrAttribute};
+
+    fn element(
+        tag: &str,
+        attributes: Vec<(&str, ValueKind)>,
+        children: Vec<IrElement>,
+    ) -> IrElement {
+        let mut element = IrElement::new(get_or_intern(tag));
+        element.attributes = attributes
+            .into_iter()
+            .map(|(key, value)| IrAttribute { key: get_or_intern(key), value })
+            .collect();
+        element.children = children.into_iter().map(IrNode::Element).collect();
+        element
+    }
+
+    fn string(s: &str) -> ValueKind {
+        ValueKind::String(s.to_string())
+    }
+
+    #[test]
+    fn property_names_are_camel_case() {
+        assert_eq!(property_name("name"), "name");
+        assert_eq!(property_name("date_published"), "datePublished");
+        assert_eq!(property_name("item_list_element"), "itemListElement");
+        assert_eq!(property_name("_private"), "private");
+        assert_eq!(property_name("type"), "@type");
+        assert_eq!(property_name("id"), "@id");
+        assert_eq!(property_name("context"), "@context");
+    }
+
+    #[test]
+    fn escapes_the_end_of_the_script() {
+        let data =
+            element(JSON_LD_BLOCK, vec![("name", string("</script><script>alert(1)"))], vec![]);
+        let json = to_json_ld(&data);
+
+        assert!(!json.contains("</"));
+        assert!(json.contains(r#""name":"<\/script><script>alert(1)""#));
+    }
+
+    #[test]
+    fn context_defaults_to_schema_org() {
+        let data = element(JSON_LD_BLOCK, vec![("type", string("Person"))], vec![]);
+        assert_eq!(to_json_ld(&data), r#"{"@context":"https://schema.org","@type":"Person"}"#);
+
+        let data = element(JSON_LD_BLOCK, vec![("context", string("https://example.org"))], vec![]);
+        assert_eq!(to_json_ld(&data), r#"{"@context":"https://example.org"}"#);
+    }
+
+    #[test]
+    fn repeated_blocks_become_arrays() {
+        let data = element(
+            JSON_LD_BLOCK,
+            vec![("type", string("BreadcrumbList"))],
+            vec![
+                element("item_list_element", vec![("position", ValueKind::Number(1.0))], vec![]),
+                element("item_list_element", vec![("position", ValueKind::Number(2.5))], vec![]),
+                element("author", vec![("is_person", ValueKind::Boolean(true))], vec![]),
+            ],
+        );
+
+        let json: Json = serde_json::from_str(&to_json_ld(&data)).unwrap();
+        assert_eq!(json["itemListElement"][0]["position"], 1);
+        assert_eq!(json["itemListElement"][1]["position"], 2.5);
+        assert_eq!(json["author"]["isPerson"], true);
+    }
+}
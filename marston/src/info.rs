@@ -3,14 +3,19 @@ pub struct BlockId(pub usize);
 
 use crate::{
     MPath, Span,
-    ast::{Block, Interned, MarstonDocument, Node},
+    ast::{Block, Interned, MarstonDocument, Node, ident_table::get_or_intern},
+    html::structured_data::JSON_LD_BLOCK,
+    validator::dispatch::Dispatcher,
 };
 use lasso::Spur;
-use std::collections::{HashMap, HashSet};
+use std::{collections::HashMap, sync::Arc};
+use url::Url;
 
 #[derive(Debug)]
 pub struct BlockInfo {
     pub id: usize,
+    /// Id assigned to the block by the parser
+    pub block_id: usize,
     pub name: Interned,
     pub span: Span,
     pub depth: usize,
@@ -18,28 +23,57 @@ pub struct BlockInfo {
     pub children: Vec<usize>,
 }
 
+#[derive(Debug)]
+pub struct IdInfo {
+    pub value: String,
+    pub span: Span,
+}
+
 #[derive(Debug)]
 pub struct Info {
     blocks: Vec<BlockInfo>,
     name_index: HashMap<Spur, Vec<usize>>,
+    block_index: HashMap<usize, usize>,
+    ids: Vec<IdInfo>,
+    id_index: HashMap<String, usize>,
     id_counter: usize,
     current_parent: Option<usize>,
     current_depth: usize,
     pub no_head: bool,
+    /// Source file of the document
+    pub file: Arc<MPath>,
+    /// Directory that local references are resolved against
+    pub main_dir: MPath,
+    /// `project.base_url` from the config
+    pub base_url: Option<Url>,
+    /// Validators registered by the rules, run in one walk after all rules were called
+    pub(crate) dispatcher: Dispatcher,
 }
 
 impl Info {
-    pub fn new() -> Self {
+    pub fn new(file: Arc<MPath>, main_dir: MPath, base_url: Option<Url>) -> Self {
         Self {
             blocks: vec![],
             name_index: HashMap::new(),
+            block_index: HashMap::new(),
+            ids: vec![],
+            id_index: HashMap::new(),
             id_counter: 0,
             current_parent: None,
             current_depth: 0,
             no_head: false,
+            file,
+            main_dir,
+            base_url,
+            dispatcher: Dispatcher::default(),
         }
     }
 
+    /// Whether the page is served over HTTPS according to `project.base_url`.
+    pub fn is_https(&self) -> bool {
+        self.base_url.as_ref().is_some_and(|url| url.scheme() == "https")
+    }
+
     pub fn has_block(&self, name: Spur) -> bool {
         self.name_index.contains_key(&name)
     }
@@ -65,19 +99,70 @@ impl Info {
         })
     }
 
+    pub fn parent_of(&self, block: &Block) -> Option<&BlockInfo> {
+        let info = &self.blocks[*self.block_index.get(&block.id)?];
+        info.parent.map(|parent| &self.blocks[parent])
+    }
+
+    /// Blocks enclosing the given block, starting with its parent.
+    pub fn ancestors(&self, block: &Block) -> Vec<&BlockInfo> {
+        let mut ancestors = vec![];
+        let mut current = self.block_index.get(&block.id).map(|&i| &self.blocks[i]);
+
+        while let Some(parent) = current.and_then(|b| b.parent).map(|p| &self.blocks[p]) {
+            ancestors.push(parent);
+            current = Some(parent);
+        }
+
+        ancestors
+    }
+
+    /// Every `id` attribute in document order, including duplicates.
+    pub fn ids(&self) -> &[IdInfo] {
+        &self.ids
+    }
+
+    pub fn has_id(&self, id: &str) -> bool {
+        self.id_index.contains_key(id)
+    }
+
+    /// Whether the block is a `.json_ld` block or inside one. Their `id` is the JSON-LD `@id`,
+    /// not the id of an element.
+    pub fn in_structured_data(&self, block: &Block) -> bool {
+        let json_ld = get_or_intern(JSON_LD_BLOCK);
+
+        block.name.as_ref().is_some_and(|name| name.key == json_ld)
+            || self.ancestors(block).iter().any(|ancestor| ancestor.name.key == json_ld)
+    }
+
+    fn collect_id(&mut self, block: &Block) {
+        if self.in_structured_data(block) {
+            return;
+        }
+
+        if let Some(attr) = block.get_attribute("id")
+            && let Some(value) = attr.value.kind.as_string()
+        {
+            let value = value.trim().to_string();
+            self.id_index.entry(value.clone()).or_insert(self.ids.len());
+            self.ids.push(IdInfo { value, span: attr.value.span.clone() });
+        }
+    }
+
     fn next_id(&mut self) -> usize {
         let id = self.id_counter;
         self.id_counter += 1;
         id
     }
 
-    fn enter_block(&mut self, block: Block) -> usize {
+    fn enter_block(&mut self, block: &Block) -> Option<usize> {
         let id = self.next_id();
 
         let info = BlockInfo {
             id,
-            name: block.name.expect("block should have name"),
-            span: block.span,
+            block_id: block.id,
+            name: block.name.clone().expect("block should have name"),
+            span: block.span.clone(),
             depth: self.current_depth,
             parent: self.current_parent,
             children: vec![],
@@ -88,17 +173,19 @@ impl Info {
         }
 
         self.name_index.entry(info.name.key).or_default().push(id);
+        self.block_index.insert(info.block_id, id);
         self.blocks.push(info);
+        self.collect_id(block);
 
         self.current_depth += 1;
         let old_parent = self.current_parent;
         self.current_parent = Some(id);
-        old_parent.unwrap_or(id)
+        old_parent
     }
 
-    fn exit_block(&mut self, old_parent: usize) {
+    fn exit_block(&mut self, old_parent: Option<usize>) {
         self.current_depth -= 1;
-        self.current_parent = Some(old_parent);
+        self.current_parent = old_parent;
     }
 
     pub fn blocks(&self) -> &[BlockInfo] {
@@ -120,7 +207,7 @@ impl InfoWalker for MarstonDocument {
 
 impl InfoWalker for Block {
     fn collect_info(&mut self, info: &mut Info) {
-        let old_parent = info.enter_block(self.clone());
+        let old_parent = info.enter_block(self);
 
         for child in &mut self.children {
             child.collect_info(info);
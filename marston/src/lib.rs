@@ -8,12 +8,16 @@ mod ast;
 mod codegen;
 pub mod config;
 pub mod context;
+mod data;
 pub mod fs;
 pub mod html;
 mod info;
 pub mod lexer;
+pub mod plugin;
 mod reports;
+mod site;
 mod span;
+mod suggestions;
 mod validator;
 
 pub type MResult<T> = anyhow::Result<T>;
@@ -0,0 +1,62 @@
+//! Extension point for binaries that wrap `marston-core` with their own checks and transforms.
+//!
+//! ```no_run
+//! use marston_core::{
+//!     MPath, MResult,
+//!     context::Context,
+//!     plugin::{GenericValidator, Info, MarstonDocument, Plugin, ValidationRule},
+//! };
+//!
+//! #[derive(Debug)]
+//! struct CompanyPlugin;
+//!
+//! impl Plugin for CompanyPlugin {
+//!     fn name(&self) -> &str {
+//!         "company"
+//!     }
+//!
+//!     fn rules(&self) -> Vec<ValidationRule<MarstonDocument>> {
+//!         vec![require_button_type]
+//!     }
+//! }
+//!
+//! fn require_button_type(doc: &MarstonDocument, info: &mut Info) {
+//!     GenericValidator::new("type").as_attribute().on_element("button").required().register(info);
+//! }
+//!
+//! fn main() -> MResult<()> {
+//!     let mut ctx = Context::new(&MPath::from("."))?;
+//!     ctx.register_plugin(CompanyPlugin);
+//!     ctx.process_file(&MPath::from("src/index.mr"))?;
+//!     ctx.finish()
+//! }
+//! ```
+
+pub use crate::{
+    ast::{Block, MarstonDocument, selector::Selector},
+    html::ir::{IrElement, IrNode, IrTransformation},
+    info::Info,
+    site::front_matter::FrontMatter,
+    validator::{
+        GenericValidator, ValidationRule,
+        conditions::{
+            AncestorIs, And, AttributeEquals, AttributeIn, Condition, ConditionResult,
+            HasAttribute, HasChild, Not, Or, ParentIs, ValidationContext,
+        },
+    },
+};
+use std::fmt::Debug;
+
+pub trait Plugin: Debug + Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Rules that run after the built-in document rules.
+    fn rules(&self) -> Vec<ValidationRule<MarstonDocument>> {
+        vec![]
+    }
+
+    /// Transformations applied to the HTML IR after the built-in ones.
+    fn transformations(&self) -> Vec<Box<dyn IrTransformation>> {
+        vec![]
+    }
+}
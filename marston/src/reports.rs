@@ -1,8 +1,10 @@
 use crate::{MPath, Span};
-use ariadne::{Report, Source};
+use ariadne::{Cache, Report, sources};
 use once_cell::sync::Lazy;
+use rustc_hash::FxHashSet;
 use std::{
     borrow::Cow,
+    io::Write,
     ops::Range,
     sync::{Arc, Mutex},
 };
@@ -14,12 +16,28 @@ pub struct ReportsBag {
     reports: Vec<MReport>,
     file: Arc<MPath>,
     source_content: Arc<str>,
+    /// Other files labels point into, such as data files
+    sources: Vec<(Arc<MPath>, Arc<str>)>,
     pub has_errors: bool,
 }
 
 impl ReportsBag {
     fn new(file_name: Arc<MPath>, source_content: Arc<str>) -> Self {
-        Self { reports: Vec::new(), file: file_name, source_content, has_errors: false }
+        Self {
+            reports: Vec::new(),
+            file: file_name,
+            source_content,
+            sources: Vec::new(),
+            has_errors: false,
+        }
+    }
+
+    /// The current file and the other files labels point into.
+    fn cache(&self) -> impl Cache<Arc<MPath>> {
+        sources(
+            std::iter::once((Arc::clone(&self.file), Arc::clone(&self.source_content)))
+                .chain(self.sources.iter().cloned()),
+        )
     }
 
     fn global_mut() -> std::sync::MutexGuard<'static, Self> {
@@ -35,11 +53,44 @@ impl ReportsBag {
         Self::global_mut().reports.push(report);
     }
 
+    /// Makes `file` available to the labels of the reports of the current file.
+    pub fn add_source(file: Arc<MPath>, content: Arc<str>) {
+        let mut bag = Self::global_mut();
+        if bag.file != file && bag.sources.iter().all(|(source, _)| *source != file) {
+            bag.sources.push((file, content));
+        }
+    }
+
     pub fn print() {
         let bag = Self::global_mut();
+        let mut cache = bag.cache();
         for report in &bag.reports {
-            let _ =
-                report.eprint((Arc::clone(&bag.file), Source::from(bag.source_content.clone())));
+            let _ = report.eprint(&mut cache);
+        }
+    }
+
+    /// Prints the reports that are not in `seen` yet, after calling `heading` if there are any.
+    /// Pages generated from one template share most of their reports, which are only printed for
+    /// the first of them.
+    pub fn print_unseen(seen: &mut FxHashSet<Vec<u8>>, heading: impl FnOnce()) {
+        let bag = Self::global_mut();
+        let mut cache = bag.cache();
+
+        let unseen = bag
+            .reports
+            .iter()
+            .filter_map(|report| {
+                let mut rendered = vec![];
+                let _ = report.write(&mut cache, &mut rendered);
+                seen.insert(rendered.clone()).then_some(rendered)
+            })
+            .collect::<Vec<_>>();
+
+        if !unseen.is_empty() {
+            heading();
+        }
+        for rendered in unseen {
+            let _ = std::io::stderr().write_all(&rendered);
         }
     }
 
@@ -62,6 +113,60 @@ impl ReportsBag {
     pub fn file() -> Arc<MPath> {
         Arc::clone(&Self::global_mut().file)
     }
+
+    /// Runs `validate` against an empty bag and returns the reports it added, rendered. The bag
+    /// is global, so tests looking at it take turns.
+    #[cfg(test)]
+    pub(crate) fn capture(validate: impl FnOnce()) -> Vec<String> {
+        static TURN: Mutex<()> = Mutex::new(());
+        let _turn = TURN.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        Self::init(Arc::new(MPath::from("index.mr")), Arc::from(""));
+        validate();
+
+        let bag = Self::global_mut();
+        let mut cache = bag.cache();
+        bag.reports
+            .iter()
+            .map(|report| {
+                let mut rendered = vec![];
+                let _ = report.write(&mut cache, &mut rendered);
+                String::from_utf8_lossy(&rendered).into_owned()
+            })
+            .collect()
+    }
+}
+
+/// Reports that span multiple files, collected after every page has been processed.
+#[derive(Default)]
+pub struct SiteReports {
+    reports: Vec<MReport>,
+    has_errors: bool,
+}
+
+impl SiteReports {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, report: MReport) {
+        self.reports.push(report);
+    }
+
+    pub fn mark_errors(&mut self) {
+        self.has_errors = true;
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.has_errors
+    }
+
+    pub fn print(&self, files: impl IntoIterator<Item = (Arc<MPath>, Arc<str>)>) {
+        let mut cache = sources(files);
+        for report in &self.reports {
+            let _ = report.eprint(&mut cache);
+        }
+    }
 }
 
 pub static REPORTS_BAG: Lazy<Mutex<ReportsBag>> = Lazy::new(|| {
@@ -84,8 +189,7 @@ macro_rules! report {
         $(, notes: [$($note:expr),* $(,)?])?
         $(,)?
     ) => {{
-        #[allow(unused_mut)]
-        let mut report = Report::build($kind, (ReportsBag::file(), Span::default()))
+        let report = Report::build($kind, (ReportsBag::file(), Span::default()))
             .with_message($message);
 
         $(
@@ -93,22 +197,22 @@ macro_rules! report {
                 let label = Label::new((ReportsBag::file(), $label_key))
                     .with_message($label_msg)
                     .with_color($label_color);
-                report = report.with_label(label);
+                let report = report.with_label(label);
             )*
         )?
 
         $(
-            for (span, msg) in $label_vec {
+            let report = $label_vec.into_iter().fold(report, |report, (span, msg)| {
                 let label = Label::new((ReportsBag::file(), span.clone()))
                     .with_message(msg)
                     .with_color(report!(@vec_color $($vec_color)?));
-                report = report.with_label(label);
-            }
+                report.with_label(label)
+            });
         )?
 
         $(
             $(
-                report = report.with_note($note);
+                let report = report.with_note($note);
             )*
         )?
 
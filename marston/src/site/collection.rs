@@ -0,0 +1,350 @@
+//! Lists of the pages in a `[[collections]]` directory.
+//!
+//! A `.collection(.name = "posts") { ... }` block repeats its children for every entry, replacing
+//! `{title}`, `{description}`, `{date}`, `{author}` and `{url}` in text and attribute values.
+//!
+//! With `.paginate = true` the list is split into pages of the collection's `page_size`. Page 2
+//! and later are written to `page/2/index.html` next to the page, and a `.pagination` block
+//! renders the links between them.
+
+use crate::{
+    ast::{
+        ValueKind,
+        ident_table::{get_or_intern, resolve},
+    },
+    config::{CollectionConfig, CollectionSort, SortOrder},
+    html::ir::{IrAttribute, IrDoc, IrElement, IrNode, IrTransformation},
+    site::{Page, PageText, Site, date::DateTime, url_dir},
+};
+use std::cmp::Ordering;
+
+pub const COLLECTION_BLOCK: &str = "collection";
+pub const PAGINATION_BLOCK: &str = "pagination";
+
+/// Metadata of an entry, available as placeholders and for `filter_*` attributes.
+pub const ENTRY_FIELDS: &[&str] = &["title", "description", "date", "author", "url"];
+pub const SORT_FIELDS: &[&str] = &["date", "title", "author", "url"];
+
+/// Whether `text` is a template filled in for every entry, such as `"{url}"`.
+pub fn has_placeholder(text: &str) -> bool {
+    ENTRY_FIELDS.iter().any(|field| text.contains(&format!("{{{field}}}")))
+}
+
+/// How a `.collection` block lists its entries, read from its attributes.
+struct ListOptions<'a> {
+    config: &'a CollectionConfig,
+    sort: CollectionSort,
+    order: SortOrder,
+    limit: Option<usize>,
+    paginate: bool,
+    /// Field and the value it must have, dates only have to start with the value
+    filters: Vec<(String, String)>,
+}
+
+impl<'a> ListOptions<'a> {
+    fn new(element: &IrElement, configs: &'a [CollectionConfig]) -> Option<Self> {
+        let string = |name: &str| element.get_attribute(get_or_intern(name))?.value.as_string();
+        let name = string("name")?;
+        let config = configs.iter().find(|config| &config.name == name)?;
+
+        let sort = match string("sort").map(String::as_str) {
+            Some("title") => CollectionSort::Title,
+            Some("author") => CollectionSort::Author,
+            Some("url") => CollectionSort::Url,
+            Some("date") => CollectionSort::Date,
+            _ => config.sort_by,
+        };
+        let default_order =
+            if sort == CollectionSort::Date { SortOrder::Desc } else { SortOrder::Asc };
+        let order = match string("order").map(String::as_str) {
+            Some("asc") => SortOrder::Asc,
+            Some("desc") => SortOrder::Desc,
+            _ if sort == config.sort_by => config.order.unwrap_or(default_order),
+            _ => default_order,
+        };
+
+        #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let limit = element
+            .get_attribute(get_or_intern("limit"))
+            .and_then(|attr| attr.value.as_number())
+            .map(|limit| limit as usize);
+        let paginate = element
+            .get_attribute(get_or_intern("paginate"))
+            .is_some_and(|attr| matches!(attr.value, ValueKind::Boolean(true)));
+
+        let filters = element
+            .attributes
+            .iter()
+            .filter_map(|attr| {
+                let field = resolve(attr.key).strip_prefix("filter_")?.to_string();
+                Some((field, attr.value.as_string()?.trim().to_string()))
+            })
+            .collect();
+
+        Some(Self { config, sort, order, limit, paginate, filters })
+    }
+
+    /// Entries of the collection, filtered and sorted but not limited or paginated.
+    fn entries<'s>(&self, site: &'s Site) -> Vec<&'s Page> {
+        let mut entries: Vec<&Page> = site
+            .pages()
+            .iter()
+            .filter(|page| page.emitted && self.config.contains(&page.url))
+            .filter(|page| {
+                self.filters.iter().all(|(field, value)| matches_filter(page, field, value))
+            })
+            .collect();
+
+        entries
+            .sort_by(|a, b| compare(a, b, self.sort, self.order).then_with(|| a.url.cmp(&b.url)));
+
+        entries
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.config.page_size.filter(|_| self.paginate)
+    }
+}
+
+/// Entries without the field sort after the ones that have it, in either order.
+fn compare(a: &Page, b: &Page, sort: CollectionSort, order: SortOrder) -> Ordering {
+    fn present<T: Ord>(a: Option<T>, b: Option<T>, order: SortOrder) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) if order == SortOrder::Desc => b.cmp(&a),
+            (Some(a), Some(b)) => a.cmp(&b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        }
+    }
+
+    let date = |page: &Page| {
+        page.date
+            .as_ref()
+            .and_then(|date| DateTime::parse(&date.value))
+            .map(|date| date.timestamp())
+    };
+    let lowercase = |text: &Option<PageText>| text.as_ref().map(|text| text.value.to_lowercase());
+
+    match sort {
+        CollectionSort::Date => present(date(a), date(b), order),
+        CollectionSort::Title => present(lowercase(&a.title), lowercase(&b.title), order),
+        CollectionSort::Author => present(lowercase(&a.author), lowercase(&b.author), order),
+        CollectionSort::Url => present(Some(&a.url), Some(&b.url), order),
+    }
+}
+
+fn matches_filter(page: &Page, field: &str, value: &str) -> bool {
+    let actual = field_value(page, field);
+    if field == "date" { actual.starts_with(value) } else { actual.eq_ignore_ascii_case(value) }
+}
+
+fn field_value(page: &Page, field: &str) -> String {
+    let text = |text: &Option<PageText>| text.as_ref().map(|t| t.value.clone());
+    match field {
+        "title" => text(&page.title),
+        "description" => text(&page.description),
+        "date" => text(&page.date),
+        "author" => text(&page.author),
+        "url" => Some(format!("/{}", page.link_path())),
+        _ => None,
+    }
+    .unwrap_or_default()
+}
+
+/// Number of pages the list page is split into, 1 unless it has a paginated collection.
+pub fn page_count(ir: &IrDoc, site: &Site, configs: &[CollectionConfig]) -> usize {
+    let mut lists = vec![];
+    for node in &ir.root {
+        if let IrNode::Element(element) = node {
+            find_collections(element, &mut lists);
+        }
+    }
+
+    lists
+        .iter()
+        .filter_map(|element| ListOptions::new(element, configs))
+        .find_map(|options| {
+            let size = options.page_size()?;
+            Some(options.entries(site).len().div_ceil(size).max(1))
+        })
+        .unwrap_or(1)
+}
+
+fn find_collections<'a>(element: &'a IrElement, lists: &mut Vec<&'a IrElement>) {
+    if resolve(element.tag) == COLLECTION_BLOCK {
+        lists.push(element);
+    }
+
+    for child in &element.children {
+        if let IrNode::Element(child) = child {
+            find_collections(child, lists);
+        }
+    }
+}
+
+/// Output path of page `number` of the list page at `url`.
+pub fn paginated_url(url: &str, number: usize) -> String {
+    if number <= 1 {
+        return url.to_string();
+    }
+
+    format!("{}page/{number}/index.html", url_dir(url))
+}
+
+/// Root-relative link to page `number` of the list page at `url`.
+fn page_link(url: &str, number: usize) -> String {
+    let path = paginated_url(url, number);
+    format!("/{}", path.strip_suffix("index.html").unwrap_or(&path))
+}
+
+/// Expands the `.collection` and `.pagination` blocks of page `number` of the list page at `url`.
+pub struct CollectionTransform<'a> {
+    pub site: &'a Site,
+    pub configs: &'a [CollectionConfig],
+    pub url: &'a str,
+    pub number: usize,
+    pub pages: usize,
+}
+
+impl IrTransformation for CollectionTransform<'_> {
+    fn apply(&self, element: &mut IrElement) {
+        let children = std::mem::take(&mut element.children);
+
+        for child in children {
+            match child {
+                IrNode::Element(child) if resolve(child.tag) == COLLECTION_BLOCK => {
+                    element.children.extend(self.expand_list(&child));
+                }
+                IrNode::Element(child) if resolve(child.tag) == PAGINATION_BLOCK => {
+                    element.children.extend(self.pagination(&child).map(IrNode::Element));
+                }
+                IrNode::Element(mut child) => {
+                    self.apply(&mut child);
+                    element.children.push(IrNode::Element(child));
+                }
+                _ => element.children.push(child),
+            }
+        }
+    }
+}
+
+impl CollectionTransform<'_> {
+    fn expand_list(&self, list: &IrElement) -> Vec<IrNode> {
+        let Some(options) = ListOptions::new(list, self.configs) else {
+            return vec![];
+        };
+
+        let entries = options.entries(self.site);
+        let entries = match options.page_size() {
+            Some(size) => entries.into_iter().skip((self.number - 1) * size).take(size).collect(),
+            None => entries,
+        };
+
+        entries
+            .into_iter()
+            .take(options.limit.unwrap_or(usize::MAX))
+            .flat_map(|page| list.children.iter().map(|node| fill_placeholders(node, page)))
+            .collect()
+    }
+
+    /// `<nav>` with links to the previous and next page, nothing if the list fits on one page.
+    fn pagination(&self, block: &IrElement) -> Option<IrElement> {
+        if self.pages <= 1 {
+            return None;
+        }
+
+        let label = |name: &str, default: &str| {
+            block
+                .get_attribute(get_or_intern(name))
+                .and_then(|attr| attr.value.as_string().cloned())
+                .unwrap_or_else(|| default.to_string())
+        };
+        let link = |rel: &str, number: usize, text: String| {
+            let mut a = IrElement::new(get_or_intern("a"));
+            a.attributes.push(attribute("rel", rel));
+            a.attributes.push(attribute("href", &page_link(self.url, number)));
+            a.children.push(IrNode::Text(text));
+            IrNode::Element(a)
+        };
+
+        let mut nav = IrElement::new(get_or_intern("nav"));
+        nav.attributes = block
+            .attributes
+            .iter()
+            .filter(|attr| !matches!(resolve(attr.key).as_str(), "previous" | "next"))
+            .cloned()
+            .collect();
+        if nav.get_attribute(get_or_intern("class")).is_none() {
+            nav.attributes.push(attribute("class", PAGINATION_BLOCK));
+        }
+
+        if self.number > 1 {
+            nav.children.push(link("prev", self.number - 1, label("previous", "Previous")));
+        }
+        let mut current = IrElement::new(get_or_intern("span"));
+        current.attributes.push(attribute("aria-current", "page"));
+        current.children.push(IrNode::Text(format!("Page {} of {}", self.number, self.pages)));
+        nav.children.push(IrNode::Element(current));
+        if self.number < self.pages {
+            nav.children.push(link("next", self.number + 1, label("next", "Next")));
+        }
+
+        Some(nav)
+    }
+}
+
+fn attribute(key: &str, value: &str) -> IrAttribute {
+    IrAttribute { key: get_or_intern(key), value: ValueKind::String(value.to_string()) }
+}
+
+fn fill_placeholders(node: &IrNode, page: &Page) -> IrNode {
+    let fill = |text: &str| {
+        ENTRY_FIELDS.iter().fold(text.to_string(), |text, field| {
+            let placeholder = format!("{{{field}}}");
+            if text.contains(&placeholder) {
+                text.replace(&placeholder, &field_value(page, field))
+            } else {
+                text
+            }
+        })
+    };
+
+    match node {
+        IrNode::Element(element) => IrNode::Element(IrElement {
+            tag: element.tag,
+            attributes: element
+                .attributes
+                .iter()
+                .map(|attr| IrAttribute {
+                    key: attr.key,
+                    value: match &attr.value {
+                        ValueKind::String(s) => ValueKind::String(fill(s)),
+                        value => value.clone(),
+                    },
+                })
+                .collect(),
+            children: element.children.iter().map(|child| fill_placeholders(child, page)).collect(),
+        }),
+        IrNode::Text(text) => IrNode::Text(fill(text)),
+        IrNode::Raw(text) => IrNode::Raw(text.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_pages_are_written_below_the_list_page() {
+        assert_eq!(paginated_url("posts/index.html", 1), "posts/index.html");
+        assert_eq!(paginated_url("posts/index.html", 2), "posts/page/2/index.html");
+        assert_eq!(paginated_url("index.html", 3), "page/3/index.html");
+        assert_eq!(paginated_url("blog/all.html", 2), "blog/page/2/index.html");
+    }
+
+    #[test]
+    fn page_links_are_root_relative() {
+        assert_eq!(page_link("posts/index.html", 1), "/posts/");
+        assert_eq!(page_link("posts/index.html", 2), "/posts/page/2/");
+        assert_eq!(page_link("blog/all.html", 1), "/blog/all.html");
+    }
+}
@@ -0,0 +1,245 @@
+//! Dates written in ISO 8601, e.g. `2024-05-31` or `2024-05-31T08:00:00+02:00`.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] =
+    ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    /// Offset from UTC in minutes, dates without one are taken as UTC
+    pub offset: i32,
+}
+
+impl DateTime {
+    /// Parses a date with an optional time and offset. Fractions of a second are dropped.
+    pub fn parse(value: &str) -> Option<Self> {
+        static RE: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(
+                r"^(\d{4})-(\d{2})-(\d{2})(?:T(\d{2}):(\d{2})(?::(\d{2})(?:\.\d+)?)?(Z|([+-])(\d{2}):(\d{2}))?)?$",
+            )
+            .unwrap()
+        });
+
+        let captures = RE.captures(value.trim())?;
+        let number = |i: usize| captures.get(i).map_or(Some(0), |m| m.as_str().parse().ok());
+
+        let offset = match captures.get(8) {
+            Some(sign) => {
+                let (hours, minutes) = (number(9)?, number(10)?);
+                if hours > 23 || minutes > 59 {
+                    return None;
+                }
+                let minutes = i32::try_from(hours * 60 + minutes).ok()?;
+                if sign.as_str() == "-" { -minutes } else { minutes }
+            }
+            None => 0,
+        };
+
+        let date = Self {
+            year: captures[1].parse().ok()?,
+            month: number(2)?,
+            day: number(3)?,
+            hour: number(4)?,
+            minute: number(5)?,
+            second: number(6)?,
+            offset,
+        };
+
+        let valid = (1..=12).contains(&date.month)
+            && (1..=days_in_month(date.year, date.month)).contains(&date.day)
+            && date.hour < 24
+            && date.minute < 60
+            && date.second < 60;
+        valid.then_some(date)
+    }
+
+    /// The UTC date and time `secs` seconds after 1970-01-01.
+    pub fn from_timestamp(secs: i64) -> Self {
+        let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+        let time = u32::try_from(secs.rem_euclid(86_400)).unwrap_or_default();
+
+        Self {
+            year,
+            month,
+            day,
+            hour: time / 3600,
+            minute: time / 60 % 60,
+            second: time % 60,
+            offset: 0,
+        }
+    }
+
+    pub fn now() -> Self {
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        Self::from_timestamp(i64::try_from(secs).unwrap_or_default())
+    }
+
+    /// Seconds since 1970-01-01 UTC, used to order dates written with different offsets.
+    pub fn timestamp(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * 86_400
+            + i64::from(self.hour * 3600 + self.minute * 60 + self.second)
+            - i64::from(self.offset) * 60
+    }
+
+    /// `YYYY-MM-DD`
+    pub fn to_date_string(self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// Format used by Atom, e.g. `2024-05-31T08:00:00+02:00`
+    pub fn to_rfc3339(self) -> String {
+        let offset = if self.offset == 0 {
+            "Z".to_string()
+        } else {
+            let sign = if self.offset < 0 { '-' } else { '+' };
+            format!("{sign}{:02}:{:02}", self.offset.abs() / 60, self.offset.abs() % 60)
+        };
+
+        format!(
+            "{}T{:02}:{:02}:{:02}{offset}",
+            self.to_date_string(),
+            self.hour,
+            self.minute,
+            self.second
+        )
+    }
+
+    /// Format used by RSS, e.g. `Fri, 31 May 2024 08:00:00 +0200`
+    pub fn to_rfc2822(self) -> String {
+        let days = days_from_civil(self.year, self.month, self.day);
+        let weekday = WEEKDAYS[usize::try_from(days.rem_euclid(7)).unwrap_or_default()];
+        let month = MONTHS[self.month as usize - 1];
+        let sign = if self.offset < 0 { '-' } else { '+' };
+
+        format!(
+            "{weekday}, {:02} {month} {:04} {:02}:{:02}:{:02} {sign}{:02}{:02}",
+            self.day,
+            self.year,
+            self.hour,
+            self.minute,
+            self.second,
+            self.offset.abs() / 60,
+            self.offset.abs() % 60
+        )
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Converts days since 1970-01-01 into a date of the proleptic Gregorian calendar.
+///
+/// See https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = u32::try_from(doy - (153 * mp + 2) / 5 + 1).unwrap_or_default();
+    let month = u32::try_from(if mp < 10 { mp + 3 } else { mp - 9 }).unwrap_or_default();
+    let year = era * 400 + yoe + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// The inverse of [`civil_from_days`].
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from(if month > 2 { month - 3 } else { month + 9 });
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dates_and_times() {
+        let date = DateTime::parse("2024-05-31").unwrap();
+        assert_eq!((date.year, date.month, date.day, date.hour, date.offset), (2024, 5, 31, 0, 0));
+
+        let date = DateTime::parse(" 2024-05-31T08:30 ").unwrap();
+        assert_eq!((date.hour, date.minute, date.second), (8, 30, 0));
+
+        let date = DateTime::parse("2024-05-31T08:30:15.250Z").unwrap();
+        assert_eq!((date.second, date.offset), (15, 0));
+
+        assert_eq!(DateTime::parse("2024-05-31T08:00:00+02:00").unwrap().offset, 120);
+        assert_eq!(DateTime::parse("2024-05-31T08:00:00-05:30").unwrap().offset, -330);
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for value in [
+            "",
+            "2024-5-31",
+            "31.05.2024",
+            "2024-13-01",
+            "2024-00-10",
+            "2023-02-29",
+            "2024-04-31",
+            "2024-05-31T24:00",
+            "2024-05-31T08:60",
+            "2024-05-31T08:00:00+24:00",
+            "2024-05-31T08:00:00+02:60",
+            "2024-05-31 08:00",
+        ] {
+            assert_eq!(DateTime::parse(value), None, "'{value}' should be rejected");
+        }
+
+        assert!(DateTime::parse("2024-02-29").is_some());
+        assert!(DateTime::parse("2000-02-29").is_some());
+        assert!(DateTime::parse("1900-02-29").is_none());
+    }
+
+    #[test]
+    fn timestamps_account_for_the_offset() {
+        let utc = DateTime::parse("2024-05-31T06:00:00Z").unwrap();
+        let local = DateTime::parse("2024-05-31T08:00:00+02:00").unwrap();
+
+        assert_eq!(utc.timestamp(), local.timestamp());
+        assert_eq!(DateTime::parse("1970-01-01").unwrap().timestamp(), 0);
+        assert_eq!(DateTime::from_timestamp(utc.timestamp()), utc);
+        assert_eq!(DateTime::from_timestamp(-86_400).to_date_string(), "1969-12-31");
+    }
+
+    #[test]
+    fn formats_for_feeds() {
+        let date = DateTime::parse("2024-05-31T08:00:00+02:00").unwrap();
+        assert_eq!(date.to_rfc3339(), "2024-05-31T08:00:00+02:00");
+        assert_eq!(date.to_rfc2822(), "Fri, 31 May 2024 08:00:00 +0200");
+
+        let date = DateTime::parse("2024-01-07T23:59:59-01:30").unwrap();
+        assert_eq!(date.to_rfc3339(), "2024-01-07T23:59:59-01:30");
+        assert_eq!(date.to_rfc2822(), "Sun, 07 Jan 2024 23:59:59 -0130");
+
+        assert_eq!(DateTime::parse("2024-05-31").unwrap().to_rfc3339(), "2024-05-31T00:00:00Z");
+    }
+}
@@ -0,0 +1,189 @@
+//! Atom and RSS feeds of the pages selected by a `[[feeds]]` entry.
+//!
+//! See https://www.rfc-editor.org/rfc/rfc4287 and https://www.rssboard.org/rss-specification
+
+use crate::{
+    codegen::Codegen,
+    config::FeedConfig,
+    reports::SiteReports,
+    site::{Page, Site, date::DateTime, escape_xml},
+};
+use ariadne::{Color, Label, Report, ReportKind};
+use itertools::Itertools;
+use std::sync::Arc;
+use url::Url;
+
+pub struct FeedEntry<'a> {
+    pub page: &'a Page,
+    pub date: DateTime,
+}
+
+/// The most recent pages of the feed, newest first. Pages without a valid date are left out.
+pub fn feed_entries<'a>(site: &'a Site, feed: &FeedConfig) -> Vec<FeedEntry<'a>> {
+    site.pages()
+        .iter()
+        .filter(|page| page.emitted && feed.matches(&page.url))
+        .filter_map(|page| {
+            let date = DateTime::parse(&page.date.as_ref()?.value)?;
+            Some(FeedEntry { page, date })
+        })
+        .sorted_by(|a, b| {
+            b.date.timestamp().cmp(&a.date.timestamp()).then_with(|| a.page.url.cmp(&b.page.url))
+        })
+        .take(feed.limit)
+        .collect()
+}
+
+/// Pages of a feed need a date to be ordered. Index pages usually list the entries and are
+/// skipped.
+pub fn validate_feed_pages(site: &Site, feeds: &[FeedConfig], reports: &mut SiteReports) {
+    for feed in feeds {
+        for page in site.pages().iter().filter(|page| feed.matches(&page.url)) {
+            if page.date.is_some() || page.source_url.ends_with("index.html") {
+                continue;
+            }
+
+            let span = page.title.as_ref().map(|title| title.span.clone()).unwrap_or_default();
+            reports.add(
+                Report::build(ReportKind::Warning, (Arc::clone(&page.source), span.clone()))
+                    .with_message(format!(
+                        "Page '{}' is left out of the feed '{}'",
+                        page.url, feed.title
+                    ))
+                    .with_label(
+                        Label::new((Arc::clone(&page.source), span))
+                            .with_message("page has no date")
+                            .with_color(Color::BrightYellow),
+                    )
+                    .with_note("Feeds are ordered by date. Add `.date = \"2024-05-31\"` to '.head'")
+                    .finish(),
+            );
+        }
+    }
+}
+
+pub fn generate_atom(feed: &FeedConfig, entries: &[FeedEntry], base_url: &Url) -> Codegen {
+    let updated = entries.first().map_or_else(DateTime::now, |entry| entry.date);
+
+    let mut p = Codegen::new();
+    p.writeln(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    p.writeln(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+    p.top_level = false;
+    p.indent();
+
+    p.writeln(&format!("<title>{}</title>", escape_xml(&feed.title)));
+    if let Some(description) = &feed.description {
+        p.writeln(&format!("<subtitle>{}</subtitle>", escape_xml(description)));
+    }
+    if let Some(path) = &feed.atom
+        && let Ok(url) = base_url.join(path)
+    {
+        p.writeln(&format!(r#"<link rel="self" href="{}"/>"#, escape_xml(url.as_str())));
+    }
+    p.writeln(&format!(r#"<link href="{}"/>"#, escape_xml(base_url.as_str())));
+    p.writeln(&format!("<id>{}</id>", escape_xml(base_url.as_str())));
+    p.writeln(&format!("<updated>{}</updated>", updated.to_rfc3339()));
+    if let Some(author) = &feed.author {
+        write_atom_author(&mut p, author);
+    }
+
+    for entry in entries {
+        let Ok(url) = base_url.join(entry.page.link_path()) else {
+            continue;
+        };
+
+        p.writeln("<entry>");
+        p.indent();
+        p.writeln(&format!("<title>{}</title>", escape_xml(&entry_title(entry.page))));
+        p.writeln(&format!(r#"<link href="{}"/>"#, escape_xml(url.as_str())));
+        p.writeln(&format!("<id>{}</id>", escape_xml(url.as_str())));
+        p.writeln(&format!("<published>{}</published>", entry.date.to_rfc3339()));
+        p.writeln(&format!("<updated>{}</updated>", entry.date.to_rfc3339()));
+        if let Some(author) = &entry.page.author {
+            write_atom_author(&mut p, &author.value);
+        }
+        if let Some(description) = &entry.page.description {
+            p.writeln(&format!("<summary>{}</summary>", escape_xml(&description.value)));
+        }
+        p.dedent();
+        p.writeln("</entry>");
+    }
+
+    p.dedent();
+    p.writeln("</feed>");
+
+    p
+}
+
+fn write_atom_author(p: &mut Codegen, name: &str) {
+    p.writeln("<author>");
+    p.indent();
+    p.writeln(&format!("<name>{}</name>", escape_xml(name)));
+    p.dedent();
+    p.writeln("</author>");
+}
+
+pub fn generate_rss(feed: &FeedConfig, entries: &[FeedEntry], base_url: &Url) -> Codegen {
+    let mut p = Codegen::new();
+    p.writeln(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    // RSS only allows an email address as `author`, names are written as `dc:creator`
+    p.writeln(
+        r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/elements/1.1/">"#,
+    );
+    p.top_level = false;
+    p.indent();
+    p.writeln("<channel>");
+    p.indent();
+
+    p.writeln(&format!("<title>{}</title>", escape_xml(&feed.title)));
+    p.writeln(&format!("<link>{}</link>", escape_xml(base_url.as_str())));
+    // `description` is required, the title is better than nothing
+    p.writeln(&format!(
+        "<description>{}</description>",
+        escape_xml(feed.description.as_ref().unwrap_or(&feed.title))
+    ));
+    if let Some(path) = &feed.rss
+        && let Ok(url) = base_url.join(path)
+    {
+        p.writeln(&format!(
+            r#"<atom:link href="{}" rel="self" type="application/rss+xml"/>"#,
+            escape_xml(url.as_str())
+        ));
+    }
+    if let Some(entry) = entries.first() {
+        p.writeln(&format!("<lastBuildDate>{}</lastBuildDate>", entry.date.to_rfc2822()));
+    }
+
+    for entry in entries {
+        let Ok(url) = base_url.join(entry.page.link_path()) else {
+            continue;
+        };
+
+        p.writeln("<item>");
+        p.indent();
+        p.writeln(&format!("<title>{}</title>", escape_xml(&entry_title(entry.page))));
+        p.writeln(&format!("<link>{}</link>", escape_xml(url.as_str())));
+        p.writeln(&format!(r#"<guid isPermaLink="true">{}</guid>"#, escape_xml(url.as_str())));
+        p.writeln(&format!("<pubDate>{}</pubDate>", entry.date.to_rfc2822()));
+        if let Some(author) = entry.page.author.as_ref().map(|a| &a.value).or(feed.author.as_ref())
+        {
+            p.writeln(&format!("<dc:creator>{}</dc:creator>", escape_xml(author)));
+        }
+        if let Some(description) = &entry.page.description {
+            p.writeln(&format!("<description>{}</description>", escape_xml(&description.value)));
+        }
+        p.dedent();
+        p.writeln("</item>");
+    }
+
+    p.dedent();
+    p.writeln("</channel>");
+    p.dedent();
+    p.writeln("</rss>");
+
+    p
+}
+
+fn entry_title(page: &Page) -> String {
+    page.title.as_ref().map_or_else(|| page.url.clone(), |title| title.value.clone())
+}
@@ -0,0 +1,172 @@
+//! Build metadata of a page, declared by a `.page(...)` block at the top of the file.
+//!
+//! The block is never rendered. Its attributes change how and whether the page is written, and
+//! plugins read the rest with [`FrontMatter::new`].
+
+use crate::{
+    ast::{MarstonDocument, ValueKind},
+    site::PageText,
+};
+use camino::Utf8Path;
+
+pub const PAGE_BLOCK: &str = "page";
+pub const PAGE_ATTRIBUTES: &[&str] =
+    &["permalink", "pretty_url", "layout", "draft", "date", "tags", "priority", "noindex"];
+
+#[derive(Debug, Clone, Default)]
+pub struct FrontMatter {
+    /// Path the page is served under instead of the one of its file, e.g. `/about/`
+    pub permalink: Option<String>,
+    /// Overrides `build.pretty_urls` for the page
+    pub pretty_url: Option<bool>,
+    /// Reserved, the build doesn't apply layouts yet and warns about it
+    pub layout: Option<String>,
+    /// Drafts are checked but not written
+    pub draft: bool,
+    pub date: Option<PageText>,
+    pub tags: Vec<String>,
+    /// Priority of the page in the sitemap
+    pub priority: Option<f64>,
+    /// Leaves the page out of the sitemap
+    pub noindex: bool,
+}
+
+impl FrontMatter {
+    pub fn new(doc: &MarstonDocument) -> Self {
+        let Some(block) = doc.front_matter() else {
+            return Self::default();
+        };
+
+        let string = |attr: &str| {
+            let value = block.get_attribute(attr)?.value.kind.as_string()?.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        let flag = |attr: &str| {
+            block.get_attribute(attr).is_some_and(|attr| attr.value.kind.as_boolean() == Some(true))
+        };
+
+        Self {
+            permalink: string("permalink"),
+            pretty_url: block
+                .get_attribute("pretty_url")
+                .and_then(|attr| attr.value.kind.as_boolean()),
+            layout: string("layout"),
+            draft: flag("draft"),
+            date: block.get_attribute("date").and_then(|attr| {
+                let value = attr.value.kind.as_string()?.trim();
+                Some(PageText { value: value.to_string(), span: attr.value.span.clone() })
+            }),
+            tags: block
+                .get_attribute("tags")
+                .and_then(|attr| attr.value.kind.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|tag| match &tag.kind {
+                    ValueKind::String(tag) if !tag.trim().is_empty() => {
+                        Some(tag.trim().to_string())
+                    }
+                    _ => None,
+                })
+                .collect(),
+            priority: block.get_attribute("priority").and_then(|attr| attr.value.kind.as_number()),
+            noindex: flag("noindex"),
+        }
+    }
+
+    /// Output path relative to the build directory of the page whose file is served at `url`.
+    ///
+    /// A valid permalink wins, permalinks without an extension are directories and get an
+    /// `index.html`. Otherwise pretty urls write `about.html` to `about/index.html`.
+    pub fn output_url(&self, url: &str, pretty_urls: bool) -> String {
+        if let Some(permalink) = self.permalink.as_ref().filter(|p| permalink_problem(p).is_none())
+        {
+            let path = permalink.trim_start_matches('/');
+
+            return if path.is_empty() || path.ends_with('/') {
+                format!("{path}index.html")
+            } else if Utf8Path::new(path).extension().is_some() {
+                path.to_string()
+            } else {
+                format!("{path}/index.html")
+            };
+        }
+
+        if self.pretty_url.unwrap_or(pretty_urls) { pretty_url(url) } else { url.to_string() }
+    }
+}
+
+/// `about.html` as `about/index.html`. Index pages already are, and hosts look for the
+/// `404.html` in the root.
+pub fn pretty_url(url: &str) -> String {
+    match url.strip_suffix(".html") {
+        Some(stem) if url != "404.html" && stem != "index" && !stem.ends_with("/index") => {
+            format!("{stem}/index.html")
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// Why `permalink` is not a path from the root of the site, if it isn't one.
+pub fn permalink_problem(permalink: &str) -> Option<&'static str> {
+    if !permalink.starts_with('/') {
+        Some("must start with '/'")
+    } else if permalink.split('/').any(|segment| segment == "..") {
+        Some("can't leave the build directory")
+    } else if permalink.contains(['?', '#', '\\']) || permalink.contains(char::is_whitespace) {
+        Some("can't contain '?', '#', backslashes or whitespace")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permalink(permalink: &str) -> FrontMatter {
+        FrontMatter { permalink: Some(permalink.to_string()), ..FrontMatter::default() }
+    }
+
+    #[test]
+    fn pretty_urls_are_directories() {
+        assert_eq!(pretty_url("about.html"), "about/index.html");
+        assert_eq!(pretty_url("blog/post.html"), "blog/post/index.html");
+        assert_eq!(pretty_url("index.html"), "index.html");
+        assert_eq!(pretty_url("blog/index.html"), "blog/index.html");
+        assert_eq!(pretty_url("404.html"), "404.html");
+        assert_eq!(pretty_url("blog/404.html"), "blog/404/index.html");
+        assert_eq!(pretty_url("feed.xml"), "feed.xml");
+    }
+
+    #[test]
+    fn output_urls_follow_the_page() {
+        let plain = FrontMatter::default();
+        assert_eq!(plain.output_url("about.html", false), "about.html");
+        assert_eq!(plain.output_url("about.html", true), "about/index.html");
+
+        let opt_out = FrontMatter { pretty_url: Some(false), ..FrontMatter::default() };
+        assert_eq!(opt_out.output_url("about.html", true), "about.html");
+        let opt_in = FrontMatter { pretty_url: Some(true), ..FrontMatter::default() };
+        assert_eq!(opt_in.output_url("about.html", false), "about/index.html");
+    }
+
+    #[test]
+    fn output_urls_follow_permalinks() {
+        assert_eq!(permalink("/").output_url("about.html", false), "index.html");
+        assert_eq!(permalink("/team/").output_url("about.html", false), "team/index.html");
+        assert_eq!(permalink("/team").output_url("about.html", false), "team/index.html");
+        assert_eq!(permalink("/team.html").output_url("about.html", true), "team.html");
+        // invalid permalinks are reported and ignored
+        assert_eq!(permalink("team/").output_url("about.html", false), "about.html");
+        assert_eq!(permalink("/../team/").output_url("about.html", true), "about/index.html");
+    }
+
+    #[test]
+    fn finds_permalink_problems() {
+        assert_eq!(permalink_problem("/blog/hello.html"), None);
+        assert!(permalink_problem("blog/").is_some());
+        assert!(permalink_problem("/blog/../../").is_some());
+        assert!(permalink_problem("/blog/?page=2").is_some());
+        assert!(permalink_problem("/my blog/").is_some());
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{
+    Span,
+    config::CollectionConfig,
+    reports::SiteReports,
+    site::{Site, resolve_link, route::is_route_template},
+    suggestions::did_you_mean,
+};
+use ariadne::{Color, Label, Report, ReportKind};
+use camino::Utf8Path;
+use std::{collections::HashSet, sync::Arc};
+
+/// Pages that are expected to be reached without a link from another page.
+const ENTRY_PAGES: &[&str] = &["index.html", "404.html"];
+
+pub fn validate_internal_links(site: &Site, reports: &mut SiteReports) {
+    for page in site.pages() {
+        for link in &page.links {
+            // links to files that don't exist are reported while validating the page itself
+            let Some(resolved) = resolve_link(page, &link.href) else {
+                continue;
+            };
+            let Some(target) = site.find_page(&resolved.url) else {
+                continue;
+            };
+            let Some(fragment) = resolved.fragment.filter(|f| !f.is_empty()) else {
+                continue;
+            };
+
+            if target.ids.contains(fragment) {
+                continue;
+            }
+
+            let note = match did_you_mean(fragment, target.ids.iter().map(String::as_str)) {
+                Some(suggestion) => format!("Did you mean '#{suggestion}'?"),
+                None => format!("Add `.id = \"{fragment}\"` to a block in '{}'", target.source),
+            };
+
+            reports.add(
+                Report::build(ReportKind::Error, (Arc::clone(&page.source), link.span.clone()))
+                    .with_message(format!(
+                        "Link points to '#{fragment}' which does not exist on '{}'",
+                        target.url
+                    ))
+                    .with_label(
+                        Label::new((Arc::clone(&page.source), link.span.clone()))
+                            .with_message(format!(
+                                "no element with id '{fragment}' on '{}'",
+                                target.url
+                            ))
+                            .with_color(Color::BrightRed),
+                    )
+                    .with_note(note)
+                    .finish(),
+            );
+            reports.mark_errors();
+        }
+    }
+}
+
+/// Links to `.mr` files are rewritten to the page built from the file, which has to be written.
+/// Files that don't exist are reported while validating the page itself.
+pub fn validate_source_links(site: &Site, reports: &mut SiteReports) {
+    for page in site.pages() {
+        for link in &page.links {
+            let Some(resolved) = resolve_link(page, &link.href) else {
+                continue;
+            };
+            if Utf8Path::new(&resolved.url).extension() != Some("mr") {
+                continue;
+            }
+
+            let (label, note) = match site.find_page(&resolved.url) {
+                Some(target) if target.front_matter.draft => (
+                    format!("'{}' is a draft", target.source),
+                    "Drafts are not written. Remove `.draft = true` from its `.page` block or the link"
+                        .to_string(),
+                ),
+                None if is_route_template(&resolved.url) => (
+                    "a route template is not a page".to_string(),
+                    "Link to one of the pages generated from the template instead".to_string(),
+                ),
+                // pages with errors and colliding pages are reported already
+                _ => continue,
+            };
+
+            reports.add(
+                Report::build(ReportKind::Error, (Arc::clone(&page.source), link.span.clone()))
+                    .with_message(format!("Link to '{}' points to no page", link.href))
+                    .with_label(
+                        Label::new((Arc::clone(&page.source), link.span.clone()))
+                            .with_message(label)
+                            .with_color(Color::BrightRed),
+                    )
+                    .with_note(note)
+                    .finish(),
+            );
+            reports.mark_errors();
+        }
+    }
+}
+
+pub fn validate_orphan_pages(
+    site: &Site,
+    collections: &[CollectionConfig],
+    reports: &mut SiteReports,
+) {
+    let mut linked: HashSet<&str> = HashSet::new();
+
+    // entries are linked from the pages listing their collection
+    let listed = site.pages().iter().flat_map(|page| &page.lists).collect::<HashSet<_>>();
+    for collection in collections.iter().filter(|collection| listed.contains(&collection.name)) {
+        linked.extend(
+            site.pages()
+                .iter()
+                .filter(|page| collection.contains(&page.url))
+                .map(|page| page.url.as_str()),
+        );
+    }
+
+    for page in site.pages() {
+        for link in &page.links {
+            if let Some(resolved) = resolve_link(page, &link.href)
+                && let Some(target) = site.find_page(&resolved.url)
+                && target.url != page.url
+            {
+                linked.insert(&target.url);
+            }
+        }
+    }
+
+    for page in site.pages() {
+        // drafts are not published, so nothing links to them yet
+        if linked.contains(page.url.as_str())
+            || ENTRY_PAGES.contains(&page.url.as_str())
+            || page.front_matter.draft
+        {
+            continue;
+        }
+
+        reports.add(
+            Report::build(ReportKind::Warning, (Arc::clone(&page.source), Span::default()))
+                .with_message(format!("Page '{}' is not linked from any other page", page.url))
+                .with_note(format!(
+                    "Link to it from another page or remove '{}' from the site",
+                    page.source
+                ))
+                .finish(),
+        );
+    }
+}
@@ -0,0 +1,435 @@
+use crate::{
+    MPath, Span,
+    ast::{
+        Block, MarstonDocument, Node,
+        ident_table::{get_or_intern, resolve},
+    },
+    config::CollectionConfig,
+    info::Info,
+    reports::SiteReports,
+    site::front_matter::FrontMatter,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use url::Url;
+
+pub mod collection;
+pub mod date;
+pub mod feed;
+pub mod front_matter;
+mod links;
+pub mod robots;
+pub mod route;
+mod seo;
+pub mod sitemap;
+pub mod urls;
+
+/// Everything the site-wide checks need to know about a page once it has been parsed.
+#[derive(Debug)]
+pub struct Page {
+    pub source: Arc<MPath>,
+    pub content: Arc<str>,
+    /// Output path relative to the build directory, always separated by '/'. Follows the
+    /// permalink of the page if it has one.
+    pub url: String,
+    /// Path of the page's file relative to the main directory with `.html` instead of `.mr`.
+    /// Links are written against it and rewritten to `url`.
+    pub source_url: String,
+    pub ids: HashSet<String>,
+    pub links: Vec<PageLink>,
+    /// `head.title`, used to find pages that share their title
+    pub title: Option<PageText>,
+    /// `head.description`, used to find pages that share their description
+    pub description: Option<PageText>,
+    /// `head.date` or `page.date`, when the page was published. Orders the entries of feeds.
+    pub date: Option<PageText>,
+    /// `head.author`
+    pub author: Option<PageText>,
+    /// Set by a `noindex` or `none` robots directive or `page.noindex`, such pages are left out of
+    /// the sitemap
+    pub noindex: bool,
+    /// Attributes of the `.sitemap` block in `.head`
+    pub sitemap: SitemapEntry,
+    /// Names of the collections the page lists, their entries count as linked from it
+    pub lists: Vec<String>,
+    /// Build metadata from the `.page` block
+    pub front_matter: FrontMatter,
+    /// Whether the page is written to the build directory, pages with errors and drafts are not
+    pub emitted: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct SitemapEntry {
+    pub changefreq: Option<String>,
+    pub priority: Option<f64>,
+    pub lastmod: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PageText {
+    pub value: String,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct PageLink {
+    pub href: String,
+    pub span: Span,
+}
+
+impl Page {
+    pub fn new(
+        source: Arc<MPath>,
+        content: Arc<str>,
+        url: String,
+        doc: &MarstonDocument,
+        info: &Info,
+        pretty_urls: bool,
+    ) -> Self {
+        let mut links = vec![];
+        let mut lists = vec![];
+        for block in &doc.blocks {
+            collect_links(block, &mut links, &mut lists);
+        }
+
+        let head = doc.find_block_by_name(get_or_intern("head"));
+        let head_text = |attr: &str| {
+            let attr = head?.get_attribute(attr)?;
+            let value = attr.value.kind.as_string()?.trim();
+            (!value.is_empty())
+                .then(|| PageText { value: value.to_string(), span: attr.value.span.clone() })
+        };
+
+        let front_matter = FrontMatter::new(doc);
+        let mut sitemap = head
+            .and_then(|head| {
+                head.child_blocks()
+                    .find(|block| resolve(block.name().key) == sitemap::SITEMAP_BLOCK)
+            })
+            .map(|block| {
+                let string = |attr: &str| {
+                    Some(block.get_attribute(attr)?.value.kind.as_string()?.trim().to_string())
+                };
+                SitemapEntry {
+                    changefreq: string("changefreq"),
+                    priority: block
+                        .get_attribute("priority")
+                        .and_then(|p| p.value.kind.as_number()),
+                    lastmod: string("lastmod"),
+                }
+            })
+            .unwrap_or_default();
+        sitemap.priority = sitemap.priority.or(front_matter.priority);
+
+        Self {
+            source,
+            content,
+            url: front_matter.output_url(&url, pretty_urls),
+            source_url: url,
+            ids: info.ids().iter().map(|id| id.value.clone()).collect(),
+            links,
+            title: head_text("title"),
+            description: head_text("description"),
+            date: head_text("date").or_else(|| front_matter.date.clone()),
+            author: head_text("author"),
+            noindex: is_noindex(doc) || front_matter.noindex,
+            sitemap,
+            lists,
+            front_matter,
+            emitted: false,
+        }
+    }
+
+    /// Url the page is linked with, index pages are served under their directory.
+    pub fn link_path(&self) -> &str {
+        self.url.strip_suffix("index.html").unwrap_or(&self.url)
+    }
+
+    /// Directory of the page's file, links on the page are resolved against it.
+    pub fn source_dir(&self) -> &str {
+        url_dir(&self.source_url)
+    }
+}
+
+/// Directory of `url`, ending with '/' unless it is the site root.
+pub fn url_dir(url: &str) -> &str {
+    url.rfind('/').map_or("", |i| &url[..=i])
+}
+
+/// Escapes text for XML content and attribute values.
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn is_noindex(doc: &MarstonDocument) -> bool {
+    let Some(head) = doc.find_block_by_name(get_or_intern("head")) else {
+        return false;
+    };
+    let meta = head.child_blocks().filter(|block| {
+        block.name.as_ref().is_some_and(|name| resolve(name.key) == "meta")
+            && block
+                .get_attribute("name")
+                .and_then(|attr| attr.value.kind.as_string())
+                .is_some_and(|name| name == "robots")
+    });
+
+    std::iter::once(head.get_attribute("robots"))
+        .chain(meta.map(|meta| meta.get_attribute("content")))
+        .flatten()
+        .filter_map(|attr| attr.value.kind.as_string())
+        .flat_map(|value| value.split(','))
+        .any(|directive| matches!(directive.trim().to_lowercase().as_str(), "noindex" | "none"))
+}
+
+/// Collects the links of the page and the names of the collections it lists.
+fn collect_links(block: &Block, links: &mut Vec<PageLink>, lists: &mut Vec<String>) {
+    // links in lists are templates filled in for every entry
+    if block.name.as_ref().is_some_and(|name| resolve(name.key) == collection::COLLECTION_BLOCK) {
+        lists.extend(
+            block.get_attribute("name").and_then(|attr| attr.value.kind.as_string()).cloned(),
+        );
+        return;
+    }
+
+    if let Some(name) = &block.name
+        && matches!(resolve(name.key).as_str(), "a" | "area")
+        && let Some(href) = block.get_attribute("href")
+        && let Some(value) = href.value.kind.as_string()
+    {
+        links.push(PageLink { href: value.trim().to_string(), span: href.value.span.clone() });
+    }
+
+    for child in &block.children {
+        if let Node::Block(child) = child {
+            collect_links(child, links, lists);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Site {
+    pages: Vec<Page>,
+    /// Index of the first page written to a url
+    by_url: HashMap<String, usize>,
+    /// Index of the first page with a `source_url`
+    by_source_url: HashMap<String, usize>,
+}
+
+impl Site {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pages are kept sorted by url so reports don't depend on the order files were processed in.
+    pub fn add_page(&mut self, page: Page) {
+        let index = self.pages.partition_point(|p| (&p.url, &p.source) < (&page.url, &page.source));
+
+        // the pages after it move up by one, as they do in `pages`
+        for i in self.by_url.values_mut().chain(self.by_source_url.values_mut()) {
+            if *i >= index {
+                *i += 1;
+            }
+        }
+        for (map, key) in
+            [(&mut self.by_url, &page.url), (&mut self.by_source_url, &page.source_url)]
+        {
+            map.entry(key.clone()).and_modify(|i| *i = (*i).min(index)).or_insert(index);
+        }
+
+        self.pages.insert(index, page);
+    }
+
+    pub fn pages(&self) -> &[Page] {
+        &self.pages
+    }
+
+    /// Finds the page served at `url`, resolving directory urls to their index page. Pages are
+    /// also found under their `source_url` and the path of their `.mr` file.
+    pub fn find_page(&self, url: &str) -> Option<&Page> {
+        let candidates = if url.is_empty() || url.ends_with('/') {
+            vec![format!("{url}index.html")]
+        } else if let Some(stem) = url.strip_suffix(".mr") {
+            vec![format!("{stem}.html")]
+        } else {
+            vec![url.to_string(), format!("{url}/index.html")]
+        };
+
+        candidates.iter().find_map(|candidate| {
+            let index = self.by_url.get(candidate).or_else(|| self.by_source_url.get(candidate))?;
+            Some(&self.pages[*index])
+        })
+    }
+
+    /// Marks the pages written to one of `urls` as not emitted.
+    pub fn withdraw(&mut self, urls: &HashSet<String>) {
+        for page in self.pages.iter_mut().filter(|page| urls.contains(&page.url)) {
+            page.emitted = false;
+        }
+    }
+
+    /// Sources of every page, used to print reports that point into multiple files.
+    pub fn sources(&self) -> Vec<(Arc<MPath>, Arc<str>)> {
+        self.pages.iter().map(|p| (Arc::clone(&p.source), Arc::clone(&p.content))).collect()
+    }
+
+    pub fn validate(&self, collections: &[CollectionConfig]) -> SiteReports {
+        let mut reports = SiteReports::new();
+
+        links::validate_internal_links(self, &mut reports);
+        links::validate_source_links(self, &mut reports);
+        links::validate_orphan_pages(self, collections, &mut reports);
+        seo::validate_duplicate_metadata(self, &mut reports);
+        urls::validate_collisions(self, &mut reports);
+
+        reports
+    }
+}
+
+/// A link resolved against the page it appears on.
+#[derive(Debug)]
+pub struct ResolvedLink<'a> {
+    /// Target url relative to the site root
+    pub url: String,
+    pub fragment: Option<&'a str>,
+}
+
+/// Resolves `href` against the file of `page`. Returns `None` for external links and same-page fragments.
+pub fn resolve_link<'a>(page: &Page, href: &'a str) -> Option<ResolvedLink<'a>> {
+    if href.is_empty()
+        || href.starts_with('#')
+        || href.starts_with("//")
+        || Url::parse(href).is_ok()
+    {
+        return None;
+    }
+
+    let (path, fragment) = match href.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (href, None),
+    };
+    let path = path.split('?').next().unwrap_or_default();
+
+    let joined = match path.strip_prefix('/') {
+        Some(root_relative) => root_relative.to_string(),
+        None => format!("{}{path}", page.source_dir()),
+    };
+
+    Some(ResolvedLink { url: normalize_url(&joined)?, fragment })
+}
+
+/// Collapses `.` and `..` segments. Returns `None` if the url escapes the site root.
+fn normalize_url(url: &str) -> Option<String> {
+    let mut segments = vec![];
+
+    for segment in url.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    let mut normalized = segments.join("/");
+    let is_dir = matches!(url.rsplit('/').next(), Some("" | "." | ".."));
+    if is_dir && !normalized.is_empty() {
+        normalized.push('/');
+    }
+
+    Some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Page of `src/{source_url}` with `.mr` replaced, written to `url`.
+    pub(super) fn page(source_url: &str, url: &str) -> Page {
+        Page {
+            source: Arc::new(MPath::from(format!("src/{source_url}"))),
+            content: Arc::from(""),
+            url: url.to_string(),
+            source_url: source_url.to_string(),
+            ids: HashSet::new(),
+            links: vec![],
+            title: None,
+            description: None,
+            date: None,
+            author: None,
+            noindex: false,
+            sitemap: SitemapEntry::default(),
+            lists: vec![],
+            front_matter: FrontMatter::default(),
+            emitted: true,
+        }
+    }
+
+    #[test]
+    fn finds_pages_by_url_and_source() {
+        let mut site = Site::new();
+        for (source_url, url) in [
+            ("blog/post.html", "blog/post/index.html"),
+            ("about.html", "team.html"),
+            ("index.html", "index.html"),
+            ("b.html", "team.html"),
+            ("a.html", "a.html"),
+        ] {
+            site.add_page(page(source_url, url));
+        }
+        let found = |url| site.find_page(url).map(|page| page.source_url.as_str());
+
+        assert_eq!(found("a.html"), Some("a.html"));
+        assert_eq!(found(""), Some("index.html"));
+        assert_eq!(found("blog/post/"), Some("blog/post.html"));
+        assert_eq!(found("blog/post"), Some("blog/post.html"));
+        assert_eq!(found("blog/post.html"), Some("blog/post.html"));
+        assert_eq!(found("blog/post.mr"), Some("blog/post.html"));
+        // pages sharing a url are found in the order of their files
+        assert_eq!(found("team.html"), Some("about.html"));
+        assert_eq!(found("b.html"), Some("b.html"));
+        assert_eq!(found("missing.html"), None);
+    }
+
+    #[test]
+    fn normalizes_urls() {
+        assert_eq!(normalize_url("blog/./post.html").as_deref(), Some("blog/post.html"));
+        assert_eq!(normalize_url("blog/../about.html").as_deref(), Some("about.html"));
+        assert_eq!(normalize_url("blog//post.html").as_deref(), Some("blog/post.html"));
+        assert_eq!(normalize_url("blog/").as_deref(), Some("blog/"));
+        assert_eq!(normalize_url("blog/post/..").as_deref(), Some("blog/"));
+        assert_eq!(normalize_url("blog/.").as_deref(), Some("blog/"));
+        assert_eq!(normalize_url("blog/..").as_deref(), Some(""));
+        assert_eq!(normalize_url("").as_deref(), Some(""));
+        assert_eq!(normalize_url("../about.html"), None);
+        assert_eq!(normalize_url("blog/../../about.html"), None);
+    }
+
+    #[test]
+    fn resolves_links_against_the_file_of_the_page() {
+        let post = page("blog/post.html", "blog/post/index.html");
+        let resolve = |href| resolve_link(&post, href).map(|link| (link.url, link.fragment));
+
+        assert_eq!(resolve("other.html"), Some(("blog/other.html".into(), None)));
+        assert_eq!(resolve("../about.html#team"), Some(("about.html".into(), Some("team"))));
+        assert_eq!(resolve("/about.html?lang=en"), Some(("about.html".into(), None)));
+        assert_eq!(resolve("./"), Some(("blog/".into(), None)));
+        assert_eq!(resolve("/"), Some((String::new(), None)));
+        assert_eq!(resolve("../../about.html"), None);
+    }
+
+    #[test]
+    fn leaves_external_links_and_fragments_alone() {
+        let index = page("index.html", "index.html");
+
+        for href in ["", "#top", "//cdn.example.com/a.js", "https://example.com", "mailto:a@b.c"] {
+            assert!(resolve_link(&index, href).is_none(), "'{href}' should not be resolved");
+        }
+    }
+}
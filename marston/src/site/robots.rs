@@ -0,0 +1,54 @@
+//! `robots.txt` generated from the `[robots]` config section.
+//!
+//! See https://www.rfc-editor.org/rfc/rfc9309
+
+use crate::{
+    codegen::Codegen,
+    config::{RobotsConfig, RobotsRule},
+};
+use url::Url;
+
+pub const ROBOTS_FILE: &str = "robots.txt";
+
+/// `sitemap` is the absolute url of the sitemap, if one was generated.
+pub fn generate_robots(config: &RobotsConfig, sitemap: Option<&Url>) -> Codegen {
+    let mut p = Codegen::new();
+
+    if config.rules.is_empty() {
+        // an empty `Disallow` allows everything
+        p.writeln("User-agent: *");
+        p.writeln("Disallow:");
+    }
+
+    for (i, rule) in config.rules.iter().enumerate() {
+        if i > 0 {
+            p.newline();
+        }
+        write_rule(&mut p, rule);
+    }
+
+    if let Some(sitemap) = sitemap {
+        p.newline();
+        p.writeln(&format!("Sitemap: {sitemap}"));
+    }
+
+    p
+}
+
+fn write_rule(p: &mut Codegen, rule: &RobotsRule) {
+    p.writeln(&format!("User-agent: {}", rule.user_agent.trim()));
+
+    for path in &rule.allow {
+        p.writeln(&format!("Allow: {path}"));
+    }
+    for path in &rule.disallow {
+        p.writeln(&format!("Disallow: {path}"));
+    }
+    if rule.allow.is_empty() && rule.disallow.is_empty() {
+        p.writeln("Disallow:");
+    }
+
+    if let Some(delay) = rule.crawl_delay {
+        p.writeln(&format!("Crawl-delay: {delay}"));
+    }
+}
@@ -0,0 +1,131 @@
+//! Pages generated from one template, such as `products/[slug].mr`, for every record of the data
+//! declared by its `[[routes]]` entry.
+//!
+//! Every `[field]` in the path of the template is replaced with the field of the record, so the
+//! record `{ slug = "widget" }` is written to `products/widget.html`.
+
+use crate::{
+    config::RouteConfig,
+    data::{DataSet, Found},
+    reports::ReportsBag,
+};
+use ariadne::{Color, Report, ReportKind};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// `[field]` in the path of a template
+static PARAM: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[([^\[\]/]+)\]").unwrap());
+
+pub fn is_route_template(path: &str) -> bool {
+    PARAM.is_match(path)
+}
+
+pub struct RoutePage<'a> {
+    /// Output path relative to the build directory
+    pub url: String,
+    pub record: Found<'a>,
+}
+
+/// The pages of `route`, whose template is written to `url`. Records with a missing or invalid
+/// field and records written to the same path are reported and left out.
+pub fn route_pages<'a>(route: &RouteConfig, url: &str, data: &'a DataSet) -> Vec<RoutePage<'a>> {
+    let Some(records) = data.records(&route.data) else {
+        return vec![];
+    };
+
+    let mut pages: Vec<RoutePage> = vec![];
+    'records: for record in records {
+        let mut page_url = url.to_string();
+        for captures in PARAM.captures_iter(url) {
+            let field = &captures[1];
+            match record.text(field) {
+                Some(value) if is_path_segment(&value) => {
+                    page_url = page_url.replace(&captures[0], &value);
+                }
+                value => {
+                    report_field(&record, field, value.as_deref(), &route.template);
+                    continue 'records;
+                }
+            }
+        }
+
+        if let Some(first) = pages.iter().find(|page| page.url == page_url) {
+            ReportsBag::mark_errors();
+            ReportsBag::add(
+                Report::build(ReportKind::Error, record.location())
+                    .with_message(format!("More than one record is written to '{page_url}'"))
+                    .with_label(
+                        first.record.label(format!("'{}' is written there", first.record.path)),
+                    )
+                    .with_label(
+                        record.label(format!("'{}' too", record.path)).with_color(Color::BrightRed),
+                    )
+                    .with_note(format!(
+                        "The fields in the path of '{}' must be unique for every record",
+                        route.template
+                    ))
+                    .finish(),
+            );
+            continue;
+        }
+
+        pages.push(RoutePage { url: page_url, record });
+    }
+
+    pages
+}
+
+/// Values of fields become a single path segment.
+fn is_path_segment(value: &str) -> bool {
+    !value.is_empty()
+        && !matches!(value, "." | "..")
+        && !value.chars().any(|c| c == '/' || c == '\\' || c.is_whitespace())
+}
+
+fn report_field(record: &Found, field: &str, value: Option<&str>, template: &str) {
+    let (message, label, note) = match value {
+        Some(value) => (
+            format!("'{value}' can't be used in the path of a page"),
+            format!("'{}.{field}' is '{value}'", record.path),
+            "Path fields can't be empty or contain slashes or whitespace".to_string(),
+        ),
+        None => (
+            format!("Record '{}' has no '{field}'", record.path),
+            format!("'{field}' is not declared in here"),
+            format!("Every record needs a '{field}' for the path of '{template}'"),
+        ),
+    };
+
+    ReportsBag::mark_errors();
+    ReportsBag::add(
+        Report::build(ReportKind::Error, record.location())
+            .with_message(message)
+            .with_label(record.label(label).with_color(Color::BrightRed))
+            .with_note(note)
+            .finish(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_route_templates() {
+        assert!(is_route_template("products/[slug].html"));
+        assert!(is_route_template("[year]/[slug]/index.html"));
+        assert!(!is_route_template("products/index.html"));
+        assert!(!is_route_template("products/[]/index.html"));
+        assert!(!is_route_template("products/[a/b].html"));
+    }
+
+    #[test]
+    fn values_must_be_a_single_path_segment() {
+        for value in ["widget", "2024", "über-uns", "a.b", "..."] {
+            assert!(is_path_segment(value), "'{value}' should be accepted");
+        }
+        for value in ["", ".", "..", "a/b", "a\\b", "two words", "tab\there", "line\n"] {
+            assert!(!is_path_segment(value), "'{value}' should be rejected");
+        }
+    }
+}
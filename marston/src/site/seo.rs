@@ -0,0 +1,60 @@
+use crate::{
+    reports::SiteReports,
+    site::{Page, PageText, Site},
+};
+use ariadne::{Color, Label, Report, ReportKind};
+use itertools::Itertools;
+use std::sync::Arc;
+
+/// Search engines show the title and description in results, pages that share them look like
+/// duplicates and compete with each other.
+pub fn validate_duplicate_metadata(site: &Site, reports: &mut SiteReports) {
+    report_duplicates(site, reports, "title", |page| page.title.as_ref());
+    report_duplicates(site, reports, "description", |page| page.description.as_ref());
+}
+
+fn report_duplicates(
+    site: &Site,
+    reports: &mut SiteReports,
+    attr: &str,
+    text: impl Fn(&Page) -> Option<&PageText>,
+) {
+    let groups = site
+        .pages()
+        .iter()
+        .filter_map(|page| Some((text(page)?, page)))
+        .into_group_map_by(|(text, _)| text.value.to_lowercase());
+
+    // sorted so the reports don't depend on the hash map's order
+    for pages in groups.into_values().sorted_by_key(|pages| pages[0].1.url.clone()) {
+        let [(first_text, first), ..] = pages.as_slice() else {
+            continue;
+        };
+        if pages.len() < 2 {
+            continue;
+        }
+
+        let labels = pages.iter().map(|(text, page)| {
+            Label::new((Arc::clone(&page.source), text.span.clone()))
+                .with_message(format!("{attr} of '{}'", page.url))
+                .with_color(Color::BrightYellow)
+        });
+
+        reports.add(
+            Report::build(
+                ReportKind::Warning,
+                (Arc::clone(&first.source), first_text.span.clone()),
+            )
+            .with_message(format!(
+                "{} pages share the {attr} '{}'",
+                pages.len(),
+                first_text.value
+            ))
+            .with_labels(labels)
+            .with_note(format!(
+                "Search engines treat pages with the same {attr} as duplicates. Give every page its own {attr}"
+            ))
+            .finish(),
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! `sitemap.xml` listing every page written to the build directory.
+//!
+//! See https://www.sitemaps.org/protocol.html
+
+use crate::{
+    codegen::Codegen,
+    site::{Page, Site, date::DateTime, escape_xml},
+};
+use std::time::UNIX_EPOCH;
+use url::Url;
+
+pub const SITEMAP_FILE: &str = "sitemap.xml";
+
+/// Block in `.head` with the page's `changefreq`, `priority` and `lastmod`
+pub const SITEMAP_BLOCK: &str = "sitemap";
+
+pub const CHANGEFREQ_VALUES: &[&str] =
+    &["always", "hourly", "daily", "weekly", "monthly", "yearly", "never"];
+
+/// Pages with errors and pages that ask not to be indexed are left out.
+pub fn generate_sitemap(site: &Site, base_url: &Url) -> Codegen {
+    let mut p = Codegen::new();
+    p.writeln(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    p.writeln(r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#);
+    p.top_level = false;
+    p.indent();
+
+    for page in site.pages().iter().filter(|page| page.emitted && !page.noindex) {
+        let Ok(loc) = base_url.join(page.link_path()) else {
+            continue;
+        };
+
+        p.writeln("<url>");
+        p.indent();
+        p.writeln(&format!("<loc>{}</loc>", escape_xml(loc.as_str())));
+
+        if let Some(lastmod) = page.sitemap.lastmod.clone().or_else(|| modified_date(page)) {
+            p.writeln(&format!("<lastmod>{}</lastmod>", escape_xml(&lastmod)));
+        }
+        if let Some(changefreq) = &page.sitemap.changefreq {
+            p.writeln(&format!("<changefreq>{}</changefreq>", escape_xml(changefreq)));
+        }
+        if let Some(priority) = page.sitemap.priority {
+            p.writeln(&format!("<priority>{priority:.1}</priority>"));
+        }
+
+        p.dedent();
+        p.writeln("</url>");
+    }
+
+    p.dedent();
+    p.writeln("</urlset>");
+
+    p
+}
+
+/// Modification date of the page's source file, as `YYYY-MM-DD`.
+fn modified_date(page: &Page) -> Option<String> {
+    let modified = fs_err::metadata(page.source.as_std_path()).ok()?.modified().ok()?;
+    let secs = modified.duration_since(UNIX_EPOCH).ok()?.as_secs();
+
+    Some(DateTime::from_timestamp(i64::try_from(secs).ok()?).to_date_string())
+}
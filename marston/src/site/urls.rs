@@ -0,0 +1,461 @@
+//! Output paths of pages and the links between them.
+//!
+//! Links are written against the files in the main directory, e.g. `about.html` or `about.mr`
+//! for `src/about.mr`. Pages written somewhere else, by a permalink or `build.pretty_urls`, get
+//! their links rewritten to where the target page and the page itself end up. Relative urls of
+//! other files, such as stylesheets and images, follow the page to its new directory.
+//!
+//! Sites served from a directory of the host, set by `build.base_path`, get it in front of every
+//! root-relative url.
+
+use crate::{
+    Span,
+    ast::{
+        ValueKind,
+        ident_table::{get_or_intern, resolve},
+    },
+    html::ir::{IrAttribute, IrElement, IrNode, IrTransformation},
+    reports::SiteReports,
+    site::{Page, Site, collection::COLLECTION_BLOCK, resolve_link, url_dir},
+};
+use ariadne::{Color, Label, Report, ReportKind};
+use camino::Utf8Path;
+use itertools::Itertools;
+use std::{collections::HashSet, sync::Arc};
+
+/// Attributes holding a url, `srcset` holds a list of them with their sizes.
+const URL_ATTRIBUTES: &[&str] =
+    &["href", "src", "srcset", "poster", "action", "formaction", "cite"];
+
+/// Output paths shared by more than one page. None of these pages is written.
+pub fn colliding_urls(site: &Site) -> HashSet<String> {
+    site.pages()
+        .iter()
+        .filter(|page| page.emitted)
+        .duplicates_by(|page| &page.url)
+        .map(|page| page.url.clone())
+        .collect()
+}
+
+pub fn validate_collisions(site: &Site, reports: &mut SiteReports) {
+    for url in colliding_urls(site).into_iter().sorted() {
+        let pages =
+            site.pages().iter().filter(|page| page.emitted && page.url == url).collect_vec();
+        let [first, rest @ ..] = pages.as_slice() else {
+            continue;
+        };
+
+        let mut report =
+            Report::build(ReportKind::Error, (Arc::clone(&first.source), Span::default()))
+                .with_message(format!("{} pages are written to '{url}'", pages.len()))
+                .with_label(
+                    Label::new((Arc::clone(&first.source), Span::default()))
+                        .with_message(format!("'{}' is written to '{url}'", first.source))
+                        .with_color(Color::BrightRed),
+                );
+        for page in rest {
+            report = report.with_label(
+                Label::new((Arc::clone(&page.source), Span::default()))
+                    .with_message(format!("'{}' as well", page.source))
+                    .with_color(Color::BrightRed),
+            );
+        }
+
+        reports.add(
+            report
+                .with_note("Give one of them a different `.permalink` in its `.page` block")
+                .finish(),
+        );
+        reports.mark_errors();
+    }
+}
+
+/// Rewrites the urls on `page` that point to other pages to the output path of their target, and
+/// relative urls of other files to `dir`.
+pub struct LinkTransform<'a> {
+    pub site: &'a Site,
+    pub page: &'a Page,
+    /// Directory the page is written to, the one of its url or of one of its paginated pages
+    pub dir: &'a str,
+}
+
+impl IrTransformation for LinkTransform<'_> {
+    fn apply(&self, element: &mut IrElement) {
+        // links in lists are templates filled in for every entry
+        if resolve(element.tag) == COLLECTION_BLOCK {
+            return;
+        }
+
+        rewrite_urls(element, |url| rewrite_url(self.site, self.page, self.dir, url));
+
+        for child in &mut element.children {
+            if let IrNode::Element(child) = child {
+                self.apply(child);
+            }
+        }
+    }
+}
+
+/// Whether `attr` of `tag` holds urls, `data` only does on `object`.
+fn is_url_attribute(tag: &str, attr: &str) -> bool {
+    URL_ATTRIBUTES.contains(&attr) || (tag == "object" && attr == "data")
+}
+
+/// Passes every url in the attributes of `element` through `rewrite`, each candidate of a
+/// `srcset` on its own. Urls `rewrite` returns `None` for are kept.
+fn rewrite_urls(element: &mut IrElement, rewrite: impl Fn(&str) -> Option<String>) {
+    let tag = resolve(element.tag);
+
+    for attr in &mut element.attributes {
+        let key = resolve(attr.key);
+        let ValueKind::String(value) = &attr.value else {
+            continue;
+        };
+        if !is_url_attribute(&tag, &key) {
+            continue;
+        }
+
+        let rewritten =
+            if key == "srcset" { rewrite_srcset(value, &rewrite) } else { rewrite(value.trim()) };
+        if let Some(rewritten) = rewritten {
+            attr.value = ValueKind::String(rewritten);
+        }
+    }
+}
+
+/// `srcset` with the url of every candidate passed through `rewrite`, `None` if none changed.
+fn rewrite_srcset(srcset: &str, rewrite: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let mut changed = false;
+    let candidates = srcset
+        .split(',')
+        .map(str::trim)
+        .filter(|candidate| !candidate.is_empty())
+        .map(|candidate| {
+            let (url, descriptor) =
+                candidate.split_once(char::is_whitespace).unwrap_or((candidate, ""));
+            match rewrite(url) {
+                Some(url) if descriptor.trim().is_empty() => {
+                    changed = true;
+                    url
+                }
+                Some(url) => {
+                    changed = true;
+                    format!("{url} {}", descriptor.trim())
+                }
+                None => candidate.to_string(),
+            }
+        })
+        .collect_vec();
+
+    changed.then(|| candidates.join(", "))
+}
+
+/// `url` pointing to the output of the page it links to, or relative to `dir` for other files.
+/// `None` if it still works as written. Links to `.mr` files are always rewritten, they don't
+/// exist in the output.
+fn rewrite_url(site: &Site, page: &Page, dir: &str, url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    if path.is_empty() {
+        return None;
+    }
+    let resolved = resolve_link(page, url)?;
+    let page_moved = dir != page.source_dir();
+
+    let Some(target) = site.find_page(&resolved.url) else {
+        // other files keep their place, so only urls relative to the page change
+        if path.starts_with('/') || !page_moved {
+            return None;
+        }
+        return Some(format!("{}{}", relative_link(dir, &resolved.url), &url[path.len()..]));
+    };
+
+    let is_source = Utf8Path::new(path).extension() == Some("mr");
+    let moved = target.url != target.source_url || page_moved;
+    if !moved && !is_source {
+        return None;
+    }
+
+    // links to sources are relative so they keep working wherever the site is served from
+    let link = if path.starts_with('/') && !is_source {
+        format!("/{}", target.link_path())
+    } else {
+        relative_link(dir, target.link_path())
+    };
+
+    Some(format!("{link}{}", &url[path.len()..]))
+}
+
+/// Link from the directory `from` to `to`, both relative to the site root.
+fn relative_link(from: &str, to: &str) -> String {
+    let (to_dir, file) = to.rfind('/').map_or(("", to), |i| (&to[..i], &to[i + 1..]));
+    let from = from.split('/').filter(|segment| !segment.is_empty()).collect_vec();
+    let to_dir = to_dir.split('/').filter(|segment| !segment.is_empty()).collect_vec();
+    let common = from.iter().zip(&to_dir).take_while(|(a, b)| a == b).count();
+
+    let mut link = "../".repeat(from.len() - common);
+    for segment in &to_dir[common..] {
+        link.push_str(segment);
+        link.push('/');
+    }
+    link.push_str(file);
+
+    if link.is_empty() { "./".to_string() } else { link }
+}
+
+/// Prefixes root-relative urls with `base_path`. With `emit_base` the page gets a `<base>` for its
+/// directory and links to its own fragments and queries name its file, the `<base>` would send
+/// them to the directory instead.
+pub struct BasePathTransform<'a> {
+    pub base_path: &'a str,
+    /// Output path of the page relative to the build directory
+    pub url: &'a str,
+    pub emit_base: bool,
+}
+
+impl IrTransformation for BasePathTransform<'_> {
+    fn apply(&self, element: &mut IrElement) {
+        // an existing `.base` is reported while validating the page and kept
+        let adds_base = self.emit_base
+            && head(element).is_some_and(|head| {
+                !head.children.iter().any(
+                    |child| matches!(child, IrNode::Element(child) if resolve(child.tag) == "base"),
+                )
+            });
+
+        self.prefix(element, adds_base);
+
+        if adds_base && let Some(head) = head(element) {
+            let mut base = IrElement::new(get_or_intern("base"));
+            base.attributes.push(IrAttribute {
+                key: get_or_intern("href"),
+                value: ValueKind::String(format!("{}/{}", self.base_path, url_dir(self.url))),
+            });
+            head.children.insert(0, IrNode::Element(base));
+        }
+    }
+}
+
+impl BasePathTransform<'_> {
+    fn prefix(&self, element: &mut IrElement, adds_base: bool) {
+        // index pages are served at their directory, which the `<base>` points to
+        let file = self.url.rsplit('/').next().unwrap_or_default();
+        let names_file = adds_base && file != "index.html";
+
+        rewrite_urls(element, |url| {
+            if url.starts_with('/') && !url.starts_with("//") {
+                Some(format!("{}{url}", self.base_path))
+            } else if names_file && url.starts_with(['#', '?']) {
+                Some(format!("{file}{url}"))
+            } else {
+                None
+            }
+        });
+
+        for child in &mut element.children {
+            if let IrNode::Element(child) = child {
+                self.prefix(child, adds_base);
+            }
+        }
+    }
+}
+
+fn head(element: &mut IrElement) -> Option<&mut IrElement> {
+    element.children.iter_mut().find_map(|child| match child {
+        IrNode::Element(child) if resolve(child.tag) == "head" => Some(child),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::site::tests::page;
+
+    fn site(pages: &[(&str, &str)]) -> Site {
+        let mut site = Site::new();
+        for (source_url, url) in pages {
+            site.add_page(page(source_url, url));
+        }
+        site
+    }
+
+    fn rewrite(site: &Site, page_url: &str, url: &str) -> Option<String> {
+        let page = site.pages().iter().find(|page| page.url == page_url).unwrap();
+        rewrite_url(site, page, url_dir(&page.url), url)
+    }
+
+    fn element(tag: &str, attributes: &[(&str, &str)]) -> IrElement {
+        let mut element = IrElement::new(get_or_intern(tag));
+        for (key, value) in attributes {
+            element.attributes.push(IrAttribute {
+                key: get_or_intern(key),
+                value: ValueKind::String((*value).to_string()),
+            });
+        }
+        element
+    }
+
+    fn attribute(element: &IrElement, key: &str) -> String {
+        let attr = element.attributes.iter().find(|attr| resolve(attr.key) == key).unwrap();
+        let ValueKind::String(value) = &attr.value else { panic!("'{key}' is not a string") };
+        value.clone()
+    }
+
+    #[test]
+    fn relative_links_between_directories() {
+        assert_eq!(relative_link("", "about.html"), "about.html");
+        assert_eq!(relative_link("", ""), "./");
+        assert_eq!(relative_link("blog/", "blog/post.html"), "post.html");
+        assert_eq!(relative_link("blog/", "about/"), "../about/");
+        assert_eq!(relative_link("blog/post/", ""), "../../");
+        assert_eq!(relative_link("blog/post/", "blog/img/a.png"), "../img/a.png");
+        assert_eq!(relative_link("about/", "blog/post/"), "../blog/post/");
+    }
+
+    #[test]
+    fn moved_page_keeps_its_assets() {
+        let site =
+            site(&[("blog/post.html", "blog/post/index.html"), ("index.html", "index.html")]);
+
+        assert_eq!(
+            rewrite(&site, "blog/post/index.html", "cover.png"),
+            Some("../cover.png".into())
+        );
+        assert_eq!(
+            rewrite(&site, "blog/post/index.html", "../style.css?v=2"),
+            Some("../../style.css?v=2".into())
+        );
+        assert_eq!(rewrite(&site, "blog/post/index.html", "/style.css"), None);
+        assert_eq!(rewrite(&site, "blog/post/index.html", "https://example.com/a.png"), None);
+        assert_eq!(rewrite(&site, "blog/post/index.html", "#top"), None);
+        assert_eq!(rewrite(&site, "blog/post/index.html", "?page=2"), None);
+        // pages that stay in the directory of their file keep their urls
+        assert_eq!(rewrite(&site, "index.html", "blog/cover.png"), None);
+
+        let page = &site.pages()[0];
+        let transform = LinkTransform { site: &site, page, dir: url_dir(&page.url) };
+        let mut img = element("img", &[("srcset", "a.png, b.png 2x,/c.png 3x")]);
+        let mut video = element("video", &[("poster", "cover.png")]);
+        let mut object = element("object", &[("data", "chart.svg")]);
+        let mut form = element("form", &[("action", "search.php"), ("data", "kept.json")]);
+        for element in [&mut img, &mut video, &mut object, &mut form] {
+            transform.apply(element);
+        }
+
+        assert_eq!(attribute(&img, "srcset"), "../a.png, ../b.png 2x, /c.png 3x");
+        assert_eq!(attribute(&video, "poster"), "../cover.png");
+        assert_eq!(attribute(&object, "data"), "../chart.svg");
+        assert_eq!(attribute(&form, "action"), "../search.php");
+        // `data` only holds a url on `object`
+        assert_eq!(attribute(&form, "data"), "kept.json");
+    }
+
+    #[test]
+    fn srcset_is_only_rewritten_if_a_url_changes() {
+        let rebase = |url: &str| (!url.starts_with('/')).then(|| format!("../{url}"));
+
+        assert_eq!(rewrite_srcset("/a.png 1x, /b.png 2x", rebase), None);
+        assert_eq!(rewrite_srcset(" a.png  480w , ", rebase), Some("../a.png 480w".into()));
+    }
+
+    #[test]
+    fn paginated_pages_rebase_from_their_own_directory() {
+        let site = site(&[("posts/index.html", "posts/index.html")]);
+        let page = &site.pages()[0];
+
+        assert_eq!(rewrite_url(&site, page, "posts/", "cover.png"), None);
+        assert_eq!(
+            rewrite_url(&site, page, "posts/page/2/", "cover.png"),
+            Some("../../cover.png".into())
+        );
+        assert_eq!(rewrite_url(&site, page, "posts/page/2/", "index.html"), Some("../../".into()));
+    }
+
+    #[test]
+    fn links_follow_moved_pages() {
+        let site = site(&[
+            ("about.html", "about/index.html"),
+            ("blog/post.html", "blog/post.html"),
+            ("index.html", "index.html"),
+        ]);
+
+        assert_eq!(rewrite(&site, "index.html", "about.html#team"), Some("about/#team".into()));
+        assert_eq!(rewrite(&site, "index.html", "/about.html"), Some("/about/".into()));
+        assert_eq!(rewrite(&site, "index.html", "blog/post.html"), None);
+        assert_eq!(rewrite(&site, "index.html", "blog/post.mr"), Some("blog/post.html".into()));
+        assert_eq!(rewrite(&site, "about/index.html", "index.html"), Some("../".into()));
+        assert_eq!(rewrite(&site, "about/index.html", "/index.mr"), Some("../".into()));
+    }
+
+    fn document(head: Vec<IrElement>, body: Vec<IrElement>) -> IrElement {
+        let mut html = element("html", &[]);
+        for (tag, children) in [("head", head), ("body", body)] {
+            let mut parent = element(tag, &[]);
+            parent.children = children.into_iter().map(IrNode::Element).collect();
+            html.children.push(IrNode::Element(parent));
+        }
+        html
+    }
+
+    fn child<'a>(element: &'a IrElement, tag: &str) -> &'a IrElement {
+        element
+            .children
+            .iter()
+            .find_map(|child| match child {
+                IrNode::Element(child) if resolve(child.tag) == tag => Some(child),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn base_path_prefixes_root_relative_urls() {
+        let mut html = document(
+            vec![element("link", &[("href", "/main.css")])],
+            vec![
+                element(
+                    "img",
+                    &[("src", "//cdn.example.com/a.png"), ("srcset", "/a.png 1x, b.png 2x")],
+                ),
+                element("form", &[("action", "/search")]),
+                element("video", &[("poster", "/cover.png")]),
+            ],
+        );
+        BasePathTransform { base_path: "/docs", url: "blog/post.html", emit_base: false }
+            .apply(&mut html);
+
+        let (head, body) = (child(&html, "head"), child(&html, "body"));
+        assert_eq!(head.children.len(), 1);
+        assert_eq!(attribute(child(head, "link"), "href"), "/docs/main.css");
+        assert_eq!(attribute(child(body, "img"), "src"), "//cdn.example.com/a.png");
+        assert_eq!(attribute(child(body, "img"), "srcset"), "/docs/a.png 1x, b.png 2x");
+        assert_eq!(attribute(child(body, "form"), "action"), "/docs/search");
+        assert_eq!(attribute(child(body, "video"), "poster"), "/docs/cover.png");
+    }
+
+    #[test]
+    fn emitted_base_keeps_fragments_on_the_page() {
+        let links =
+            || vec![element("a", &[("href", "#top")]), element("form", &[("action", "?q=1")])];
+
+        let mut post = document(vec![], links());
+        BasePathTransform { base_path: "/docs", url: "blog/post.html", emit_base: true }
+            .apply(&mut post);
+        assert_eq!(attribute(child(child(&post, "head"), "base"), "href"), "/docs/blog/");
+        assert_eq!(attribute(child(child(&post, "body"), "a"), "href"), "post.html#top");
+        assert_eq!(attribute(child(child(&post, "body"), "form"), "action"), "post.html?q=1");
+
+        // index pages are served at the directory the base points to
+        let mut index = document(vec![], links());
+        BasePathTransform { base_path: "/docs", url: "blog/index.html", emit_base: true }
+            .apply(&mut index);
+        assert_eq!(attribute(child(child(&index, "body"), "a"), "href"), "#top");
+
+        // an existing base is kept, fragments resolve against it as written
+        let mut own_base = document(vec![element("base", &[("href", "/")])], links());
+        BasePathTransform { base_path: "/docs", url: "blog/post.html", emit_base: true }
+            .apply(&mut own_base);
+        assert_eq!(child(&own_base, "head").children.len(), 1);
+        assert_eq!(attribute(child(child(&own_base, "body"), "a"), "href"), "#top");
+    }
+}
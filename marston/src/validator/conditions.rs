@@ -1,48 +1,67 @@
 use crate::{
-    Span,
-    ast::{Block, Value},
+    ast::{Block, Node, ident_table::resolve},
+    info::Info,
     validator::GenericValidator,
 };
-use std::{collections::HashMap, fmt::Debug};
+use std::fmt::Debug;
 
+/// Outcome of a condition. `messages` describe the condition itself, so they read the same
+/// whether it passed or not, e.g. "'rel' is \"stylesheet\"".
 pub struct ConditionResult {
     pub result: bool,
-    pub messages: Vec<&'static str>,
+    pub messages: Vec<String>,
 }
 
 impl ConditionResult {
-    pub fn new(result: bool, message: Option<&'static str>) -> Self {
-        ConditionResult {
-            result,
-            messages: if let Some(msg) = message { vec![msg] } else { vec![] },
-        }
+    pub fn new(result: bool, message: Option<&str>) -> Self {
+        ConditionResult { result, messages: message.map(ToString::to_string).into_iter().collect() }
     }
 
+    #[must_use]
     pub fn join(self, other: ConditionResult) -> Self {
         ConditionResult {
             result: self.result && other.result,
             messages: self.messages.into_iter().chain(other.messages).collect(),
         }
     }
+
+    /// All messages as one sentence fragment.
+    pub fn describe(&self) -> String {
+        self.messages.join(" and ")
+    }
+
+    /// Wraps the description in parentheses if it's made of multiple parts.
+    fn describe_grouped(&self) -> String {
+        if self.messages.len() > 1 || self.describe().contains(" or ") {
+            format!("({})", self.describe())
+        } else {
+            self.describe()
+        }
+    }
 }
 
 pub trait Condition: Debug {
     fn evaluate(&self, context: &ValidationContext) -> ConditionResult;
+
+    /// Evaluates the negation of this condition. Conditions override it to describe the negation
+    /// in plain words instead of "not (...)".
+    fn evaluate_not(&self, context: &ValidationContext) -> ConditionResult {
+        let result = self.evaluate(context);
+        ConditionResult {
+            result: !result.result,
+            messages: vec![format!("not {}", result.describe_grouped())],
+        }
+    }
 }
 
 pub struct ValidationContext<'a> {
     pub block: &'a Block,
+    pub info: &'a Info,
 }
 
 impl<'a> ValidationContext<'a> {
-    pub fn new(block: &'a Block) -> Self {
-        ValidationContext { block }
-    }
-}
-
-impl<'a> Into<ValidationContext<'a>> for &'a Block {
-    fn into(self) -> ValidationContext<'a> {
-        ValidationContext::new(self)
+    pub fn new(block: &'a Block, info: &'a Info) -> Self {
+        ValidationContext { block, info }
     }
 }
 
@@ -51,6 +70,7 @@ impl<T> AttributeEqualsPredicateTrait for T where T: Fn(&Block) -> ConditionResu
 
 pub type AttributeEqualsPredicate = Box<dyn AttributeEqualsPredicateTrait>;
 
+/// Escape hatch for conditions the other building blocks can't express.
 #[derive(Debug)]
 pub struct AttributeEquals {
     pub predicate: AttributeEqualsPredicate,
@@ -74,13 +94,226 @@ pub struct And<C1: Condition, C2: Condition> {
     pub right: C2,
 }
 
+impl<C1: Condition, C2: Condition> And<C1, C2> {
+    pub fn new(left: C1, right: C2) -> Self {
+        And { left, right }
+    }
+}
+
 impl<C1: Condition, C2: Condition> Condition for And<C1, C2> {
     fn evaluate(&self, ctx: &ValidationContext) -> ConditionResult {
         self.left.evaluate(ctx).join(self.right.evaluate(ctx))
     }
+
+    fn evaluate_not(&self, ctx: &ValidationContext) -> ConditionResult {
+        either(&self.left.evaluate_not(ctx), &self.right.evaluate_not(ctx))
+    }
+}
+
+#[derive(Debug)]
+pub struct Or<C1: Condition, C2: Condition> {
+    pub left: C1,
+    pub right: C2,
+}
+
+impl<C1: Condition, C2: Condition> Or<C1, C2> {
+    pub fn new(left: C1, right: C2) -> Self {
+        Or { left, right }
+    }
+}
+
+impl<C1: Condition, C2: Condition> Condition for Or<C1, C2> {
+    fn evaluate(&self, ctx: &ValidationContext) -> ConditionResult {
+        either(&self.left.evaluate(ctx), &self.right.evaluate(ctx))
+    }
+
+    fn evaluate_not(&self, ctx: &ValidationContext) -> ConditionResult {
+        self.left.evaluate_not(ctx).join(self.right.evaluate_not(ctx))
+    }
+}
+
+fn either(left: &ConditionResult, right: &ConditionResult) -> ConditionResult {
+    ConditionResult {
+        result: left.result || right.result,
+        messages: vec![format!("{} or {}", left.describe_grouped(), right.describe_grouped())],
+    }
+}
+
+#[derive(Debug)]
+pub struct Not<C: Condition> {
+    pub inner: C,
+}
+
+impl<C: Condition> Not<C> {
+    pub fn new(inner: C) -> Self {
+        Not { inner }
+    }
+}
+
+impl<C: Condition> Condition for Not<C> {
+    fn evaluate(&self, ctx: &ValidationContext) -> ConditionResult {
+        self.inner.evaluate_not(ctx)
+    }
+
+    fn evaluate_not(&self, ctx: &ValidationContext) -> ConditionResult {
+        self.inner.evaluate(ctx)
+    }
+}
+
+#[derive(Debug)]
+pub struct HasAttribute {
+    pub name: &'static str,
+}
+
+impl HasAttribute {
+    pub fn new(name: &'static str) -> Self {
+        HasAttribute { name }
+    }
+}
+
+impl Condition for HasAttribute {
+    fn evaluate(&self, ctx: &ValidationContext) -> ConditionResult {
+        let result = ctx.block.get_attribute(self.name).is_some();
+        ConditionResult::new(result, Some(&format!("'{}' is set", self.name)))
+    }
+
+    fn evaluate_not(&self, ctx: &ValidationContext) -> ConditionResult {
+        let result = ctx.block.get_attribute(self.name).is_none();
+        ConditionResult::new(result, Some(&format!("'{}' is not set", self.name)))
+    }
+}
+
+/// Matches when the attribute is a string equal to one of `values`, ignoring ASCII case.
+#[derive(Debug)]
+pub struct AttributeIn {
+    pub name: &'static str,
+    pub values: &'static [&'static str],
+}
+
+impl AttributeIn {
+    pub fn new(name: &'static str, values: &'static [&'static str]) -> Self {
+        AttributeIn { name, values }
+    }
+
+    fn matches(&self, block: &Block) -> bool {
+        block
+            .get_attribute(self.name)
+            .and_then(|attr| attr.value.kind.as_string())
+            .is_some_and(|value| self.values.iter().any(|v| v.eq_ignore_ascii_case(value.trim())))
+    }
+
+    fn describe(&self, negated: bool) -> String {
+        let verb = if negated { "is not" } else { "is" };
+        let values = self.values.iter().map(|v| format!("\"{v}\"")).collect::<Vec<_>>();
+
+        match values.as_slice() {
+            [value] => format!("'{}' {verb} {value}", self.name),
+            _ => format!("'{}' {verb} one of {}", self.name, values.join(", ")),
+        }
+    }
+}
+
+impl Condition for AttributeIn {
+    fn evaluate(&self, ctx: &ValidationContext) -> ConditionResult {
+        ConditionResult::new(self.matches(ctx.block), Some(&self.describe(false)))
+    }
+
+    fn evaluate_not(&self, ctx: &ValidationContext) -> ConditionResult {
+        ConditionResult::new(!self.matches(ctx.block), Some(&self.describe(true)))
+    }
+}
+
+/// Matches when the direct parent block has the given name.
+#[derive(Debug)]
+pub struct ParentIs {
+    pub name: &'static str,
+}
+
+impl ParentIs {
+    pub fn new(name: &'static str) -> Self {
+        ParentIs { name }
+    }
+
+    fn matches(&self, ctx: &ValidationContext) -> bool {
+        ctx.info.parent_of(ctx.block).is_some_and(|parent| resolve(parent.name.key) == self.name)
+    }
+}
+
+impl Condition for ParentIs {
+    fn evaluate(&self, ctx: &ValidationContext) -> ConditionResult {
+        ConditionResult::new(self.matches(ctx), Some(&format!("the parent is '{}'", self.name)))
+    }
+
+    fn evaluate_not(&self, ctx: &ValidationContext) -> ConditionResult {
+        ConditionResult::new(
+            !self.matches(ctx),
+            Some(&format!("the parent is not '{}'", self.name)),
+        )
+    }
+}
+
+/// Matches when any block above this one has the given name.
+#[derive(Debug)]
+pub struct AncestorIs {
+    pub name: &'static str,
+}
+
+impl AncestorIs {
+    pub fn new(name: &'static str) -> Self {
+        AncestorIs { name }
+    }
+
+    fn matches(&self, ctx: &ValidationContext) -> bool {
+        ctx.info.ancestors(ctx.block).iter().any(|ancestor| resolve(ancestor.name.key) == self.name)
+    }
+}
+
+impl Condition for AncestorIs {
+    fn evaluate(&self, ctx: &ValidationContext) -> ConditionResult {
+        ConditionResult::new(self.matches(ctx), Some(&format!("it is inside '{}'", self.name)))
+    }
+
+    fn evaluate_not(&self, ctx: &ValidationContext) -> ConditionResult {
+        ConditionResult::new(!self.matches(ctx), Some(&format!("it is not inside '{}'", self.name)))
+    }
+}
+
+/// Matches when a direct child block has the given name.
+#[derive(Debug)]
+pub struct HasChild {
+    pub name: &'static str,
+}
+
+impl HasChild {
+    pub fn new(name: &'static str) -> Self {
+        HasChild { name }
+    }
+
+    fn matches(&self, block: &Block) -> bool {
+        block.children.iter().any(|child| {
+            matches!(child, Node::Block(child) if child.name.as_ref().is_some_and(|n| resolve(n.key) == self.name))
+        })
+    }
+}
+
+impl Condition for HasChild {
+    fn evaluate(&self, ctx: &ValidationContext) -> ConditionResult {
+        ConditionResult::new(
+            self.matches(ctx.block),
+            Some(&format!("it has a '{}' child", self.name)),
+        )
+    }
+
+    fn evaluate_not(&self, ctx: &ValidationContext) -> ConditionResult {
+        ConditionResult::new(
+            !self.matches(ctx.block),
+            Some(&format!("it has no '{}' child", self.name)),
+        )
+    }
 }
 
 impl GenericValidator {
+    #[must_use]
     pub fn required_if(mut self, condition: impl Condition + 'static) -> Self {
         self.required_condition = Some(Box::new(condition));
         self
@@ -93,8 +326,137 @@ impl GenericValidator {
         }
     }
 
+    #[must_use]
     pub fn valid_if(mut self, condition: impl Condition + 'static) -> Self {
         self.valid_if = Some(Box::new(condition));
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        MPath,
+        ast::{Attribute, Interned, MarstonDocument, Value, ValueKind, ident_table::get_or_intern},
+        info::InfoWalker,
+    };
+    use std::sync::Arc;
+
+    fn block(id: usize, name: &str, attributes: &[(&str, &str)], children: Vec<Block>) -> Block {
+        let mut block = Block::new(Some(Interned::new(get_or_intern(name), 0..0)), id);
+        block.attributes = attributes
+            .iter()
+            .map(|(key, value)| {
+                Attribute::new(
+                    Interned::new(get_or_intern(key), 0..0),
+                    Value { kind: ValueKind::String((*value).to_string()), span: 0..0 },
+                )
+            })
+            .collect();
+        block.children = children.into_iter().map(Node::Block).collect();
+        block
+    }
+
+    /// Result and description of `condition` on the link of
+    /// `head(1) { link(2)[rel=stylesheet href=main.css] }`.
+    fn evaluate(condition: &impl Condition) -> (bool, String) {
+        let link = block(2, "link", &[("rel", "stylesheet"), ("href", "main.css")], vec![]);
+        let mut doc = MarstonDocument { blocks: vec![block(1, "head", &[], vec![link])] };
+        let mut info = Info::new(Arc::new(MPath::from("index.mr")), MPath::from("."), None);
+        doc.collect_info(&mut info);
+
+        let Some(Node::Block(link)) = doc.blocks[0].children.first() else {
+            unreachable!("the head holds the link");
+        };
+        let result = condition.evaluate(&ValidationContext::new(link, &info));
+        (result.result, result.describe())
+    }
+
+    #[test]
+    fn describes_single_conditions() {
+        assert_eq!(evaluate(&HasAttribute::new("href")), (true, "'href' is set".to_string()));
+        assert_eq!(
+            evaluate(&AttributeIn::new("rel", &["STYLESHEET"])),
+            (true, "'rel' is \"STYLESHEET\"".to_string())
+        );
+        assert_eq!(
+            evaluate(&AttributeIn::new("rel", &["preload", "prefetch"])),
+            (false, "'rel' is one of \"preload\", \"prefetch\"".to_string())
+        );
+        assert_eq!(evaluate(&ParentIs::new("head")), (true, "the parent is 'head'".to_string()));
+        assert_eq!(evaluate(&AncestorIs::new("body")), (false, "it is inside 'body'".to_string()));
+        assert_eq!(
+            evaluate(&HasChild::new("script")),
+            (false, "it has a 'script' child".to_string())
+        );
+    }
+
+    #[test]
+    fn describes_negations_in_plain_words() {
+        assert_eq!(
+            evaluate(&Not::new(HasAttribute::new("media"))),
+            (true, "'media' is not set".to_string())
+        );
+        assert_eq!(
+            evaluate(&Not::new(AttributeIn::new("rel", &["stylesheet"]))),
+            (false, "'rel' is not \"stylesheet\"".to_string())
+        );
+        assert_eq!(
+            evaluate(&Not::new(AncestorIs::new("body"))),
+            (true, "it is not inside 'body'".to_string())
+        );
+        assert_eq!(
+            evaluate(&Not::new(Not::new(ParentIs::new("head")))),
+            (true, "the parent is 'head'".to_string())
+        );
+    }
+
+    #[test]
+    fn combines_descriptions() {
+        assert_eq!(
+            evaluate(&And::new(
+                HasAttribute::new("href"),
+                AttributeIn::new("rel", &["stylesheet"])
+            )),
+            (true, "'href' is set and 'rel' is \"stylesheet\"".to_string())
+        );
+        assert_eq!(
+            evaluate(&Or::new(HasAttribute::new("media"), ParentIs::new("body"))),
+            (false, "'media' is set or the parent is 'body'".to_string())
+        );
+        assert_eq!(
+            evaluate(&Or::new(
+                And::new(HasAttribute::new("href"), HasAttribute::new("media")),
+                HasChild::new("script"),
+            )),
+            (false, "('href' is set and 'media' is set) or it has a 'script' child".to_string())
+        );
+    }
+
+    #[test]
+    fn negated_combinators_follow_de_morgan() {
+        assert_eq!(
+            evaluate(&Not::new(And::new(HasAttribute::new("href"), HasAttribute::new("media")))),
+            (true, "'href' is not set or 'media' is not set".to_string())
+        );
+        assert_eq!(
+            evaluate(&Not::new(Or::new(HasAttribute::new("media"), ParentIs::new("body")))),
+            (true, "'media' is not set and the parent is not 'body'".to_string())
+        );
+    }
+
+    #[test]
+    fn negates_custom_conditions_as_a_whole() {
+        let condition = AttributeEquals::new(|block| {
+            ConditionResult::new(block.get_attribute("href").is_some(), Some("it links a file"))
+                .join(ConditionResult::new(true, Some("it is cached")))
+        });
+
+        assert_eq!(evaluate(&condition), (true, "it links a file and it is cached".to_string()));
+        assert_eq!(
+            evaluate(&Not::new(condition)),
+            (false, "not (it links a file and it is cached)".to_string())
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use crate::{
+    ast::{
+        Block, MarstonDocument,
+        ident_table::resolve,
+        selector::{Position, Selector},
+    },
+    info::Info,
+    validator::{GenericValidator, TargetType, Validate},
+};
+use lasso::Spur;
+use std::{collections::HashMap, fmt};
+
+/// Runs every registered [`GenericValidator`] in a single walk over the document, together
+/// with the rules of [`Block`], which see every block, nested ones included.
+///
+/// Validators are indexed by the block name they apply to, or by attribute key for validators
+/// that check an attribute on every block, so each block only meets the validators
+/// interested in it. Selections collect the blocks their selector matches along the way and
+/// are handed all of them once the walk is done.
+#[derive(Default)]
+pub struct Dispatcher {
+    validators: Vec<GenericValidator>,
+    /// `on_element` validators and `in_parent` validators whose selector names the parent
+    by_block: HashMap<Spur, Vec<usize>>,
+    /// `in_parent` validators whose selector can match any block, e.g. `[href]`
+    any_block: Vec<usize>,
+    /// `validate_all` attribute validators
+    by_attribute: HashMap<Spur, Vec<usize>>,
+    /// validators without a parent, which look at the top level of the document
+    root: Vec<usize>,
+    selections: Vec<Selection>,
+    /// selections by the block names their selector can match, like `by_block`
+    selections_by_block: HashMap<Spur, Vec<usize>>,
+    selections_any_block: Vec<usize>,
+}
+
+type SelectionRule = Box<dyn Fn(&[&Block], &Info)>;
+
+/// A rule over every block a selector matches, for checks that need all of them at once, e.g.
+/// to count them.
+struct Selection {
+    selector: Selector,
+    rule: SelectionRule,
+}
+
+impl Dispatcher {
+    pub fn add(&mut self, validator: GenericValidator) {
+        let index = self.validators.len();
+        debug_assert!(
+            !validator.validate_all || validator.target_type == TargetType::Attribute,
+            "`validate_all` only supports attributes, '{}' is not one",
+            resolve(validator.name)
+        );
+
+        if validator.validate_all {
+            self.by_attribute.entry(validator.name).or_default().push(index);
+        } else if let Some(element) = validator.element {
+            self.by_block.entry(element).or_default().push(index);
+        } else if let Some(selector) = &validator.parent {
+            index_subjects(selector, index, &mut self.by_block, &mut self.any_block);
+        } else {
+            self.root.push(index);
+        }
+
+        self.validators.push(validator);
+    }
+
+    /// Calls `rule` after the walk with every block `selector` matches, in document order.
+    pub fn select(
+        &mut self,
+        selector: impl Into<Selector>,
+        rule: impl Fn(&[&Block], &Info) + 'static,
+    ) {
+        let selector = selector.into();
+        let index = self.selections.len();
+        index_subjects(
+            &selector,
+            index,
+            &mut self.selections_by_block,
+            &mut self.selections_any_block,
+        );
+
+        self.selections.push(Selection { selector, rule: Box::new(rule) });
+    }
+
+    pub fn run(&self, doc: &MarstonDocument, info: &mut Info) {
+        let mut path = vec![];
+        let mut selected = vec![vec![]; self.selections.len()];
+        self.walk(doc.blocks.iter(), &mut path, &mut selected, info);
+
+        for &index in &self.root {
+            let validator = &self.validators[index];
+            validator.validate_in_document_root(doc, &resolve(validator.name), info);
+        }
+
+        for (selection, blocks) in self.selections.iter().zip(&selected) {
+            (selection.rule)(blocks, info);
+        }
+    }
+
+    fn walk<'a>(
+        &self,
+        blocks: impl Iterator<Item = &'a Block>,
+        path: &mut Vec<Position<'a>>,
+        selected: &mut [Vec<&'a Block>],
+        info: &mut Info,
+    ) {
+        for (index, block) in blocks.enumerate() {
+            let Some(name) = &block.name else {
+                continue;
+            };
+
+            path.push(Position { block, first_child: index == 0 });
+
+            block.validate(info);
+            self.dispatch(block, name.key, path, info);
+
+            let candidates = self.selections_by_block.get(&name.key).into_iter().flatten();
+            for &index in candidates.chain(&self.selections_any_block) {
+                if self.selections[index].selector.matches_path(path) {
+                    selected[index].push(block);
+                }
+            }
+
+            self.walk(block.child_blocks(), path, selected, info);
+
+            path.pop();
+        }
+    }
+
+    fn dispatch(&self, block: &Block, name: Spur, path: &[Position], info: &Info) {
+        let candidates = self.by_block.get(&name).into_iter().flatten().chain(&self.any_block);
+
+        for &index in candidates {
+            let validator = &self.validators[index];
+
+            let applies = match &validator.parent {
+                Some(selector) if validator.element.is_none() => selector.matches_path(path),
+                _ => true,
+            };
+
+            if applies {
+                validator.validate_in_block(block, info);
+            }
+        }
+
+        for attr in &block.attributes {
+            for &index in self.by_attribute.get(&attr.key.key).into_iter().flatten() {
+                self.validators[index].validate_attribute_value(attr, block, info);
+            }
+        }
+    }
+}
+
+/// Files `index` under the block names `selector` can match, or under `any` if it can match
+/// any block.
+fn index_subjects(
+    selector: &Selector,
+    index: usize,
+    by_block: &mut HashMap<Spur, Vec<usize>>,
+    any: &mut Vec<usize>,
+) {
+    match selector.subject_names() {
+        Some(names) => {
+            for name in names {
+                let indices = by_block.entry(name).or_default();
+                // selector lists can name the same block twice
+                if indices.last() != Some(&index) {
+                    indices.push(index);
+                }
+            }
+        }
+        None => any.push(index),
+    }
+}
+
+impl fmt::Debug for Dispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dispatcher")
+            .field("validators", &self.validators.len())
+            .field("selections", &self.selections.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        MPath,
+        ast::{Attribute, Interned, Node, Value, ValueKind, ident_table::get_or_intern},
+        reports::ReportsBag,
+    };
+    use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+    fn block(id: usize, name: &str, attributes: &[&str], children: Vec<Block>) -> Block {
+        let mut block = Block::new(Some(Interned::new(get_or_intern(name), 0..0)), id);
+        block.attributes = attributes
+            .iter()
+            .map(|key| {
+                Attribute::new(
+                    Interned::new(get_or_intern(key), 0..0),
+                    Value { kind: ValueKind::dummy_string(), span: 0..0 },
+                )
+            })
+            .collect();
+        block.children = children.into_iter().map(Node::Block).collect();
+        block
+    }
+
+    /// `body(1)[title] { div(2)[title] { div(3)[title] } p(4) }`
+    fn doc() -> MarstonDocument {
+        MarstonDocument {
+            blocks: vec![block(
+                1,
+                "body",
+                &["title"],
+                vec![
+                    block(2, "div", &["title"], vec![block(3, "div", &["title"], vec![])]),
+                    block(4, "p", &[], vec![]),
+                ],
+            )],
+        }
+    }
+
+    fn info() -> Info {
+        Info::new(Arc::new(MPath::from("index.mr")), MPath::from("."), None)
+    }
+
+    /// Ids of the blocks `validator` checked its attribute on, in walk order.
+    fn checked_blocks(validator: GenericValidator) -> Vec<usize> {
+        let seen = Rc::new(RefCell::new(vec![]));
+        let recorded = Rc::clone(&seen);
+        let validator =
+            validator.check_value(move |_, _, ctx| recorded.borrow_mut().push(ctx.block.id));
+
+        let mut dispatcher = Dispatcher::default();
+        dispatcher.add(validator);
+        dispatcher.run(&doc(), &mut info());
+
+        seen.take()
+    }
+
+    #[test]
+    fn on_element_matches_nested_blocks() {
+        let validator = GenericValidator::new("title").as_attribute().on_element("div");
+        assert_eq!(checked_blocks(validator), [2, 3]);
+    }
+
+    #[test]
+    fn in_parent_follows_the_selector() {
+        let validator = GenericValidator::new("title").as_attribute().in_parent("body > div");
+        assert_eq!(checked_blocks(validator), [2]);
+
+        let validator = GenericValidator::new("title").as_attribute().in_parent(vec!["body"]);
+        assert_eq!(checked_blocks(validator), [1]);
+
+        let validator = GenericValidator::new("title").as_attribute().in_parent("[title]");
+        assert_eq!(checked_blocks(validator), [1, 2, 3]);
+    }
+
+    #[test]
+    fn validate_all_checks_every_block_with_the_attribute() {
+        let validator = GenericValidator::new("title").as_attribute().validate_all();
+        assert_eq!(checked_blocks(validator), [1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "only supports attributes")]
+    fn validate_all_rejects_blocks() {
+        let mut dispatcher = Dispatcher::default();
+        dispatcher.add(GenericValidator::new("div").as_block().validate_all());
+    }
+
+    /// Ids of the blocks the selection of `selector` was handed.
+    fn selected_blocks(selector: &'static str) -> Vec<usize> {
+        let seen = Rc::new(RefCell::new(vec![]));
+        let recorded = Rc::clone(&seen);
+
+        let mut dispatcher = Dispatcher::default();
+        dispatcher.select(selector, move |blocks, _| {
+            recorded.borrow_mut().extend(blocks.iter().map(|block| block.id));
+        });
+        dispatcher.run(&doc(), &mut info());
+
+        seen.take()
+    }
+
+    #[test]
+    fn selections_get_every_match_in_document_order() {
+        assert_eq!(selected_blocks("div"), [2, 3]);
+        assert_eq!(selected_blocks("body > div"), [2]);
+        assert_eq!(selected_blocks("p, div div"), [3, 4]);
+        assert_eq!(selected_blocks("[title]"), [1, 2, 3]);
+        assert_eq!(selected_blocks("span"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn block_rules_run_on_nested_blocks() {
+        let nested = block(2, "div", &["title", "title"], vec![]);
+        let doc = MarstonDocument { blocks: vec![block(1, "body", &[], vec![nested])] };
+
+        let reports = ReportsBag::capture(|| Dispatcher::default().run(&doc, &mut info()));
+        assert!(!reports.is_empty(), "duplicate attribute of a nested block not reported");
+    }
+}
@@ -1,15 +1,16 @@
 use crate::{
     Span,
     ast::{
-        Attribute, Block, MarstonDocument, Node, Value, ValueKind,
+        Attribute, Block, MarstonDocument, Value, ValueKind,
         ident_table::{get_or_intern, resolve},
+        selector::Selector,
     },
     html::{lang::is_valid_language_pattern, tags::is_unique_tag},
     info::{BlockInfo, Info},
     report,
     reports::ReportsBag,
     validator::{
-        conditions::{AttributeEquals, Condition, ValidationContext},
+        conditions::{Condition, ValidationContext},
         url::UrlValidation,
     },
 };
@@ -20,9 +21,10 @@ use rustc_hash::FxHashSet;
 use std::{collections::HashSet, fmt::format, path::PathBuf};
 use unic_langid::LanguageIdentifier;
 
-mod conditions;
+pub mod conditions;
+pub mod dispatch;
 pub mod rules;
-mod url;
+pub mod url;
 
 pub type ValidationRule<T> = fn(&T, &mut Info);
 
@@ -48,10 +50,10 @@ pub enum TargetType {
 pub struct GenericValidator {
     name: Spur,
     target_type: TargetType,
-    parent: Option<Vec<Spur>>,
+    parent: Option<Selector>,
     required: bool,
     type_checks: Vec<Box<dyn Fn(&Value, &Span) -> bool>>,
-    value_checks: Vec<Box<dyn Fn(&Value, &Span, &Block)>>,
+    value_checks: Vec<Box<dyn Fn(&Value, &Span, &ValidationContext)>>,
     no_children: bool,
     require_on_of_attrs: Vec<String>,
     disallowed: bool,
@@ -59,6 +61,10 @@ pub struct GenericValidator {
     url_validation: Option<UrlValidation>,
     valid_if: Option<Box<dyn Condition>>,
     validate_all: bool,
+    element: Option<Spur>,
+    severity: ReportKind<'static>,
+    message: Option<String>,
+    note: Option<String>,
 }
 
 impl GenericValidator {
@@ -77,46 +83,87 @@ impl GenericValidator {
             url_validation: None,
             valid_if: None,
             validate_all: false,
+            element: None,
+            severity: ReportKind::Error,
+            message: None,
+            note: None,
         }
     }
 
-    /// validates every collected block
+    /// validates the attribute on every block it is set on. Only supported for attributes
+    #[must_use]
     pub fn validate_all(mut self) -> Self {
         self.validate_all = true;
         self
     }
 
+    #[must_use]
     pub fn as_attribute(mut self) -> Self {
         self.target_type = TargetType::Attribute;
         self
     }
 
+    #[must_use]
     pub fn as_block(mut self) -> Self {
         self.target_type = TargetType::Block;
         self
     }
 
-    pub fn in_parent(mut self, parent_names: Vec<&str>) -> Self {
-        let parent_keys: Vec<Spur> = parent_names.iter().map(|name| get_or_intern(name)).collect();
-        self.parent = Some(parent_keys);
+    /// validates every block with the given name, wherever it is in the document
+    #[must_use]
+    pub fn on_element(mut self, element: &str) -> Self {
+        self.element = Some(get_or_intern(element));
         self
     }
 
+    /// severity of missing and disallowed reports
+    #[must_use]
+    pub fn with_severity(mut self, severity: ReportKind<'static>) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// replaces the message of missing and disallowed reports
+    #[must_use]
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// replaces the note of missing and disallowed reports
+    #[must_use]
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    /// limits validation to blocks matched by the selector. A list of names is an exact chain
+    /// starting at the top level, e.g. `vec!["head", "link"]`
+    #[must_use]
+    pub fn in_parent(mut self, parent: impl Into<Selector>) -> Self {
+        self.parent = Some(parent.into());
+        self
+    }
+
+    #[must_use]
     pub fn required(mut self) -> Self {
         self.required = true;
         self
     }
 
+    #[must_use]
     pub fn require_one_of_attrs(mut self, attrs: Vec<&str>) -> Self {
         self.require_on_of_attrs = attrs.iter().map(|s| s.to_string()).collect();
         self
     }
 
+    #[must_use]
     pub fn disallowed(mut self) -> Self {
         self.disallowed = true;
         self
     }
 
+    #[must_use]
     pub fn must_be_string(mut self) -> Self {
         self.type_checks.push(Box::new(|value: &Value, span: &Span| {
             if value.kind.as_string().is_none() {
@@ -136,6 +183,7 @@ impl GenericValidator {
         self
     }
 
+    #[must_use]
     pub fn must_be_number(mut self) -> Self {
         self.type_checks.push(Box::new(|value: &Value, span: &Span| {
             if value.kind.as_number().is_none() {
@@ -155,6 +203,7 @@ impl GenericValidator {
         self
     }
 
+    #[must_use]
     pub fn must_be_boolean(mut self) -> Self {
         self.type_checks.push(Box::new(|value: &Value, span: &Span| {
             if value.kind.as_boolean().is_none() {
@@ -174,6 +223,7 @@ impl GenericValidator {
         self
     }
 
+    #[must_use]
     pub fn must_be_array(mut self, inner_ty: Option<ValueKind>) -> Self {
         self.type_checks.push(Box::new(move |value: &Value, span: &Span| {
             if let Some(array) = value.kind.as_array() {
@@ -208,14 +258,16 @@ impl GenericValidator {
         self
     }
 
+    #[must_use]
     pub fn check_value<F>(mut self, check: F) -> Self
     where
-        F: Fn(&Value, &Span, &Block) + 'static,
+        F: Fn(&Value, &Span, &ValidationContext) + 'static,
     {
         self.value_checks.push(Box::new(check));
         self
     }
 
+    #[must_use]
     pub fn string_not_empty(self) -> Self {
         self.check_value(|value, span, _| {
             if let Some(s) = value.kind.as_string()
@@ -233,6 +285,7 @@ impl GenericValidator {
         })
     }
 
+    #[must_use]
     pub fn disallowed_chars(self, disallowed: Vec<char>) -> Self {
         let disallowed: FxHashSet<char> = disallowed.into_iter().collect();
         self.check_value(move |value, span, _| {
@@ -255,6 +308,7 @@ impl GenericValidator {
         })
     }
 
+    #[must_use]
     pub fn array_not_empty(self) -> Self {
         self.check_value(|value, span, _| {
             if let Some(array) = value.kind.as_array() {
@@ -271,6 +325,7 @@ impl GenericValidator {
         })
     }
 
+    #[must_use]
     pub fn string_min_length(self, min: usize) -> Self {
         self.check_value(move |value, span, _| {
             if let Some(s) = value.kind.as_string() {
@@ -289,6 +344,7 @@ impl GenericValidator {
         })
     }
 
+    #[must_use]
     pub fn string_min_length_error(self, min: usize) -> Self {
         self.check_value(move |value, span, _| {
             if let Some(s) = value.kind.as_string() {
@@ -307,6 +363,7 @@ impl GenericValidator {
         })
     }
 
+    #[must_use]
     pub fn string_max_length(self, max: usize) -> Self {
         self.check_value(move |value, span, _| {
             if let Some(s) = value.kind.as_string() {
@@ -325,6 +382,7 @@ impl GenericValidator {
         })
     }
 
+    #[must_use]
     pub fn string_max_length_error(self, max: usize) -> Self {
         self.check_value(move |value, span, _| {
             if let Some(s) = value.kind.as_string() {
@@ -343,6 +401,7 @@ impl GenericValidator {
         })
     }
 
+    #[must_use]
     pub fn string_not_generic(self, generic_values: &'static [&'static str]) -> Self {
         self.check_value(move |value, span, _| {
             if let Some(s) = value.kind.as_string() {
@@ -361,6 +420,7 @@ impl GenericValidator {
         })
     }
 
+    #[must_use]
     pub fn string_valid_language_code(self) -> Self {
         self.check_value(|value, span, _| {
             if let Some(s) = value.kind.as_string() {
@@ -383,6 +443,7 @@ impl GenericValidator {
         })
     }
 
+    #[must_use]
     pub fn number_min(self, min: f64) -> Self {
         self.check_value(move |value, span, _| {
             if let Some(n) = value.kind.as_number() && n < min {
@@ -398,6 +459,7 @@ impl GenericValidator {
         })
     }
 
+    #[must_use]
     pub fn number_max(self, max: f64) -> Self {
         self.check_value(move |value, span, _| {
             if let Some(n) = value.kind.as_number() && n > max {
@@ -413,58 +475,33 @@ impl GenericValidator {
         })
     }
 
+    #[must_use]
     pub fn number_positive(self) -> Self {
         self.number_min(0.0)
     }
 
-    pub fn walk_block(&self, block: &Block, name_str: &str, info: &mut Info) {
-        if let Some(attr) = block.get_attribute(&resolve(self.name)) {
-            self.validate_attribute_value(&attr, block);
-        }
-
-        for child in &block.children {
-            match child {
-                Node::Block(child_block) => {
-                    self.walk_block(child_block, name_str, info);
-                }
-                _ => {}
-            }
-        }
+    /// Queues the validator on `info`. Queued validators run together in a single walk over the
+    /// document once every rule has been called, see [`Dispatcher`](dispatch::Dispatcher).
+    pub fn register(self, info: &mut Info) {
+        info.dispatcher.add(self);
     }
 
-    pub fn validate(&self, doc: &MarstonDocument, info: &mut Info) {
+    /// Validates the attribute or child blocks this validator targets on `parent`.
+    fn validate_in_block(&self, parent: &Block, info: &Info) {
         let name_str = resolve(self.name);
 
-        if self.validate_all && self.target_type == TargetType::Attribute {
-            for block in &doc.blocks {
-                self.walk_block(block, &name_str.clone(), info);
-            }
-
-            return;
-        }
-
-        if let Some(parent_keys) = &self.parent {
-            let parents = doc.find_in_parent(parent_keys);
-
-            for parent in parents {
-                let found_as_blocks = parent.find_all_child_blocks(self.name);
-                let found_as_attrs = parent.get_attribute(&name_str);
-
-                self.validate_found_items(
-                    found_as_attrs,
-                    found_as_blocks,
-                    &name_str,
-                    &resolve(parent.name().key),
-                    parent.name().span,
-                    Some(parent),
-                )
-            }
-        } else {
-            self.validate_in_document_root(doc, &name_str);
-        }
+        self.validate_found_items(
+            parent.get_attribute(&name_str),
+            parent.find_all_child_blocks(self.name),
+            &name_str,
+            &resolve(parent.name().key),
+            parent.name().span,
+            Some(parent),
+            info,
+        );
     }
 
-    fn validate_in_document_root(&self, doc: &MarstonDocument, name_str: &str) {
+    fn validate_in_document_root(&self, doc: &MarstonDocument, name_str: &str, info: &Info) {
         let mut found_as_attribute = None;
         let mut found_as_block = vec![];
 
@@ -489,10 +526,11 @@ impl GenericValidator {
             "document root",
             Span::default(),
             None,
+            info,
         );
     }
 
-    pub fn validate_block(&self, block: &Block, name_str: &str) {
+    pub fn validate_block(&self, block: &Block, name_str: &str, info: &Info) {
         if self.no_children && !block.children.is_empty() {
             ReportsBag::add(report!(
                 kind: ReportKind::Error,
@@ -510,7 +548,7 @@ impl GenericValidator {
             for attr in &required {
                 if let Some(attr) = block.get_attribute(attr) {
                     found = true;
-                    self.validate_attribute_value(&attr, block);
+                    self.validate_attribute_value(&attr, block, info);
                 }
             }
             if !found {
@@ -534,26 +572,27 @@ impl GenericValidator {
         parent_name: &str,
         parent_span: Span,
         parent: Option<&Block>,
+        info: &Info,
     ) {
         let blocks = if found_as_block.is_empty() { None } else { Some(found_as_block) };
 
         match (found_as_attribute, blocks, self.target_type) {
             (Some(attr), None, TargetType::Attribute) | (Some(attr), None, TargetType::Either) => {
-                self.validate_attribute_value(&attr, parent.unwrap());
+                self.validate_attribute_value(attr, parent.unwrap(), info);
             }
             (None, Some(blocks), TargetType::Block) | (None, Some(blocks), TargetType::Either) => {
                 for block in blocks {
                     if self.disallowed {
                         ReportsBag::add(report!(
-                            kind: ReportKind::Error,
-                            message: format!("'{}' should not be used", name_str),
+                            kind: self.severity,
+                            message: self.message.clone().unwrap_or_else(|| format!("'{name_str}' should not be used")),
                             labels: {
                                 block.name().span.clone() => format!("'{}' found as block but should not be used", name_str) => Color::BrightRed
                             },
-                            notes: [format!("Remove '{}' from '{}'", name_str, parent_name)]
+                            notes: [self.note.clone().unwrap_or_else(|| format!("Remove '{name_str}' from '{parent_name}'"))]
                         ));
                     } else {
-                        self.validate_block(block, name_str);
+                        self.validate_block(block, name_str, info);
                     }
                 }
             }
@@ -604,32 +643,38 @@ impl GenericValidator {
                     TargetType::Either => "attribute or block",
                 };
 
-                let report = report!(
-                    kind: ReportKind::Error,
-                    message: format!("Missing required {} '{}'", expected_type, name_str),
-                    labels: {
-                        parent_span => format!("'{}' {} is required in '{}'", name_str, expected_type, parent_name) => Color::BrightRed
-                    },
-                    notes: [format!("Add the required '{}' {}", name_str, expected_type)]
-                );
+                let mut note = self
+                    .note
+                    .clone()
+                    .unwrap_or_else(|| format!("Add the required '{name_str}' {expected_type}"));
 
                 if self.target_type == TargetType::Attribute
                     && let Some(parent) = parent
                     && let Some(predicate) = &self.required_condition
                 {
-                    if !predicate.evaluate(&ValidationContext::new(parent)).result {
+                    let result = predicate.evaluate(&ValidationContext::new(parent, info));
+                    if !result.result {
                         return;
                     }
 
-                    ReportsBag::add(report);
-                } else if self.required {
-                    ReportsBag::add(report);
+                    note = format!("{note}, it is required when {}", result.describe());
+                } else if !self.required {
+                    return;
                 }
+
+                ReportsBag::add(report!(
+                    kind: self.severity,
+                    message: self.message.clone().unwrap_or_else(|| format!("Missing required {expected_type} '{name_str}'")),
+                    labels: {
+                        parent_span => format!("'{}' {} is required in '{}'", name_str, expected_type, parent_name) => Color::BrightRed
+                    },
+                    notes: [note]
+                ));
             }
         }
     }
 
-    fn validate_attribute_value(&self, attr: &Attribute, block: &Block) {
+    fn validate_attribute_value(&self, attr: &Attribute, block: &Block, info: &Info) {
         let value = &attr.value;
         let span = &attr.value.span;
 
@@ -639,12 +684,13 @@ impl GenericValidator {
             }
         }
 
+        let ctx = ValidationContext::new(block, info);
         for value_check in &self.value_checks {
-            value_check(value, span, block);
+            value_check(value, span, &ctx);
         }
 
         if let Some(predicate) = &self.valid_if {
-            let result = predicate.evaluate(&ValidationContext::new(block));
+            let result = predicate.evaluate(&ctx);
 
             if !result.result {
                 ReportsBag::add(report!(
@@ -653,12 +699,13 @@ impl GenericValidator {
                     labels: {
                         span.clone() => "not valid" => Color::BrightRed
                     },
-                    notes: [result.messages.join(". ")]
+                    notes: [format!("'{}' is only valid when {}", resolve(attr.key.key), result.describe())]
                 ))
             }
         }
     }
 
+    #[must_use]
     pub fn string_file_extension(self, extension: &'static str) -> Self {
         self.check_value(move |value, span, _| {
             if let Some(s) = value.kind.as_string() {
@@ -679,6 +726,7 @@ impl GenericValidator {
         })
     }
 
+    #[must_use]
     pub fn string_allowed_values(self, allowed: &'static [&'static str], strict: bool) -> Self {
         self.check_value(move |value, span, _| {
             if let Some(s) = value.kind.as_string() {
@@ -714,6 +762,7 @@ impl GenericValidator {
         })
     }
 
+    #[must_use]
     pub fn block_no_children(mut self) -> Self {
         self.no_children = true;
         self
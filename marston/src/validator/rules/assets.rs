@@ -0,0 +1,205 @@
+use crate::{
+    MPath, Span,
+    ast::{Block, ident_table::resolve},
+    info::Info,
+    report,
+    reports::ReportsBag,
+    site::collection::has_placeholder,
+    suggestions::did_you_mean,
+    validator::{Label, Report},
+};
+use ariadne::{Color, ReportKind};
+use camino::{Utf8Component, Utf8Path};
+use url::Url;
+
+/// Elements and the attributes on them that point to a local file.
+const ASSET_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("a", &["href"]),
+    ("area", &["href"]),
+    ("audio", &["src"]),
+    ("embed", &["src"]),
+    ("iframe", &["src"]),
+    ("img", &["src"]),
+    ("input", &["src"]),
+    ("link", &["href"]),
+    ("object", &["data"]),
+    ("script", &["src"]),
+    ("source", &["src"]),
+    ("track", &["src"]),
+    ("video", &["src", "poster"]),
+];
+
+pub fn validate_local_assets(block: &Block, info: &mut Info) {
+    let Some(name) = &block.name else {
+        return;
+    };
+    let name = resolve(name.key);
+
+    if let Some((_, attributes)) = ASSET_ATTRIBUTES.iter().find(|(tag, _)| *tag == name) {
+        for attr_name in *attributes {
+            if let Some(attr) = block.get_attribute(attr_name)
+                && let Some(value) = attr.value.kind.as_string()
+            {
+                check_local_reference(value, &attr.value.span, info);
+            }
+        }
+    }
+}
+
+/// Resolves a reference against the page location or, for root-relative references, `main_dir`.
+/// Returns `None` for references that don't point to a local file.
+pub fn resolve_local_reference(value: &str, info: &Info) -> Option<MPath> {
+    let value = value.trim();
+    let path = value.split(['?', '#']).next().unwrap_or_default();
+
+    if path.is_empty() || value.starts_with("//") || Url::parse(value).is_ok() {
+        return None;
+    }
+
+    Some(match path.strip_prefix('/') {
+        Some(root_relative) => info.main_dir.join(root_relative),
+        None => info
+            .file
+            .parent()
+            .map_or_else(|| info.main_dir.clone(), Utf8Path::to_path_buf)
+            .join(path),
+    })
+}
+
+fn check_local_reference(value: &str, span: &Span, info: &Info) {
+    if has_placeholder(value) {
+        return;
+    }
+    let Some(path) = resolve_local_reference(value, info) else {
+        return;
+    };
+
+    if !is_inside_main_dir(&path, &info.main_dir) {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: format!("'{}' points outside of '{}'", value.trim(), info.main_dir),
+            labels: {
+                span.clone() => "this file will not be copied to the output directory" => Color::BrightRed
+            },
+            notes: ["Only files inside the main directory are copied to the build output"]
+        ));
+        return;
+    }
+
+    if local_file_exists(&path) {
+        return;
+    }
+
+    let file_name = path.file_name().unwrap_or_default();
+    let siblings = path.parent().map(list_dir).unwrap_or_default();
+    let suggestion = did_you_mean(file_name, siblings.iter().map(String::as_str));
+
+    let note = if let Some(suggestion) = suggestion {
+        format!("Did you mean '{}'?", with_file_name(value.trim(), suggestion))
+    } else {
+        format!("Add the file to '{}' or fix the reference", info.main_dir)
+    };
+
+    ReportsBag::add(report!(
+        kind: ReportKind::Error,
+        message: format!("Referenced file '{}' does not exist", value.trim()),
+        labels: {
+            span.clone() => format!("'{}' not found", path) => Color::BrightRed
+        },
+        notes: [note]
+    ));
+}
+
+fn is_inside_main_dir(path: &MPath, main_dir: &MPath) -> bool {
+    normalize_path(path).starts_with(normalize_path(main_dir))
+}
+
+/// Collapses `.` and `..` without touching the file system. `..` that can't be collapsed are
+/// kept.
+fn normalize_path(path: &Utf8Path) -> MPath {
+    let mut normalized = MPath::new();
+
+    for component in path.components() {
+        match component {
+            Utf8Component::CurDir => {}
+            Utf8Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Utf8Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+/// `reference` with its last path segment replaced by `file_name`, keeping the directories, the
+/// query and the fragment.
+fn with_file_name(reference: &str, file_name: &str) -> String {
+    let (path, rest) = reference.split_at(reference.find(['?', '#']).unwrap_or(reference.len()));
+    let trimmed = path.trim_end_matches('/');
+    let start = trimmed.rfind('/').map_or(0, |i| i + 1);
+
+    format!("{}{file_name}{}{rest}", &path[..start], &path[trimmed.len()..])
+}
+
+/// Pages are referenced by their output name, so `about.html` exists if `about.mr` does.
+fn local_file_exists(path: &MPath) -> bool {
+    if path.is_file() {
+        return true;
+    }
+
+    if path.is_dir() {
+        return path.join("index.mr").is_file() || path.join("index.html").is_file();
+    }
+
+    path.extension() == Some("html") && path.with_extension("mr").is_file()
+}
+
+fn list_dir(dir: &Utf8Path) -> Vec<String> {
+    let Ok(entries) = dir.read_dir_utf8() else {
+        return vec![];
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .flat_map(|entry| {
+            let name = entry.file_name().to_string();
+            // pages are referenced by their output name
+            match name.strip_suffix(".mr") {
+                Some(stem) => vec![format!("{stem}.html"), name],
+                None => vec![name],
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggestion_replaces_only_the_file_name() {
+        assert_eq!(with_file_name("img/img.pgn", "img.png"), "img/img.png");
+        assert_eq!(with_file_name("/img.png/img.pgn?v=2#x", "img.png"), "/img.png/img.png?v=2#x");
+        assert_eq!(with_file_name("dcs/", "docs"), "docs/");
+        assert_eq!(with_file_name("abot.html", "about.html"), "about.html");
+    }
+
+    #[test]
+    fn paths_are_normalized_before_the_main_dir_check() {
+        let main_dir = MPath::from("/site/src");
+
+        assert!(is_inside_main_dir(&MPath::from("/site/src/img/a.png"), &main_dir));
+        assert!(is_inside_main_dir(&MPath::from("/site/src/img/../a.png"), &main_dir));
+        assert!(is_inside_main_dir(&MPath::from("/site/./src/a.png"), &main_dir));
+        assert!(!is_inside_main_dir(&MPath::from("/site/src/../x"), &main_dir));
+        assert!(!is_inside_main_dir(&MPath::from("/site/src/img/../../x"), &main_dir));
+        assert!(!is_inside_main_dir(&MPath::from("/site/src2/a.png"), &main_dir));
+        assert!(is_inside_main_dir(&MPath::from("src/a.png"), &MPath::from("./src")));
+    }
+}
@@ -7,15 +7,31 @@ use crate::{
     info::Info,
     report,
     reports::ReportsBag,
-    validator::{Label, Report, Validate, ValidationRule},
+    validator::{
+        Label, Report, Validate, ValidationRule,
+        rules::{
+            assets::validate_local_assets, ids::validate_fragment_references,
+            obsolete::validate_obsolete, performance::validate_layout_shift,
+            security::validate_event_handlers,
+        },
+    },
 };
 use ariadne::{Color, ReportKind};
 use itertools::Itertools;
 use std::collections::HashMap;
 
+/// Block rules run on every block of the document, nested ones included, during the walk of
+/// [`Dispatcher`](crate::validator::dispatch::Dispatcher).
 impl Validate for Block {
     fn rules() -> Vec<ValidationRule<Self>> {
-        vec![validate_attributes_uniqueness]
+        vec![
+            validate_attributes_uniqueness,
+            validate_fragment_references,
+            validate_local_assets,
+            validate_event_handlers,
+            validate_layout_shift,
+            validate_obsolete,
+        ]
     }
 
     fn validate(&self, info: &mut Info) {
@@ -0,0 +1,162 @@
+use crate::{
+    Span,
+    ast::{
+        Block, MarstonDocument, ValueKind,
+        ident_table::{get_or_intern, resolve},
+    },
+    config::CollectionConfig,
+    info::Info,
+    report,
+    reports::ReportsBag,
+    site::collection::{COLLECTION_BLOCK, ENTRY_FIELDS, PAGINATION_BLOCK, SORT_FIELDS},
+    suggestions::did_you_mean,
+    validator::{GenericValidator, Label, Report},
+};
+use ariadne::{Color, ReportKind};
+use itertools::Itertools;
+
+/// Attributes of `.collection` besides the `filter_*` ones.
+const LIST_ATTRIBUTES: &[&str] = &["name", "sort", "order", "limit", "paginate"];
+
+/// Lists need the collections from `marston.toml`, so they are checked apart from the rules.
+pub fn validate_collections(_: &MarstonDocument, info: &mut Info, configs: &[CollectionConfig]) {
+    GenericValidator::new("name")
+        .as_attribute()
+        .in_parent(COLLECTION_BLOCK)
+        .required()
+        .with_note("Name the `[[collections]]` entry to list, e.g. `.name = \"posts\"`")
+        .must_be_string()
+        .register(info);
+
+    GenericValidator::new("sort")
+        .as_attribute()
+        .in_parent(COLLECTION_BLOCK)
+        .must_be_string()
+        .string_allowed_values(SORT_FIELDS, true)
+        .register(info);
+
+    GenericValidator::new("order")
+        .as_attribute()
+        .in_parent(COLLECTION_BLOCK)
+        .must_be_string()
+        .string_allowed_values(&["asc", "desc"], true)
+        .register(info);
+
+    GenericValidator::new("limit")
+        .as_attribute()
+        .in_parent(COLLECTION_BLOCK)
+        .must_be_number()
+        .number_min(1.0)
+        .register(info);
+
+    GenericValidator::new("paginate")
+        .as_attribute()
+        .in_parent(COLLECTION_BLOCK)
+        .must_be_boolean()
+        .register(info);
+
+    let configs = configs.to_vec();
+    info.dispatcher.select(COLLECTION_BLOCK, move |lists, info| check_lists(lists, &configs, info));
+}
+
+fn check_lists(lists: &[&Block], configs: &[CollectionConfig], info: &Info) {
+    let mut paginated = vec![];
+
+    for list in lists {
+        for attr in &list.attributes {
+            let name = resolve(attr.key.key);
+            let known = match name.strip_prefix("filter_") {
+                Some(field) => ENTRY_FIELDS.contains(&field),
+                None => LIST_ATTRIBUTES.contains(&name.as_str()),
+            };
+            if !known {
+                let candidates = LIST_ATTRIBUTES
+                    .iter()
+                    .map(|attr| (*attr).to_string())
+                    .chain(ENTRY_FIELDS.iter().map(|field| format!("filter_{field}")))
+                    .collect_vec();
+                let note = match did_you_mean(&name, candidates.iter().map(String::as_str)) {
+                    Some(suggestion) => format!("Did you mean '{suggestion}'?"),
+                    None => format!("Lists support {}", candidates.join(", ")),
+                };
+
+                ReportsBag::add(report!(
+                    kind: ReportKind::Error,
+                    message: format!("Unknown list attribute '{name}'"),
+                    labels: {
+                        attr.key.span.clone() => "not a list attribute" => Color::BrightRed
+                    },
+                    notes: [note]
+                ));
+            }
+        }
+
+        let Some(name) = list.get_attribute("name") else {
+            continue;
+        };
+        let Some(value) = name.value.kind.as_string() else {
+            continue;
+        };
+        let Some(config) = configs.iter().find(|config| &config.name == value) else {
+            let note = match did_you_mean(value, configs.iter().map(|config| config.name.as_str()))
+            {
+                Some(suggestion) => format!("Did you mean '{suggestion}'?"),
+                None => "Declare it in `marston.toml` with `[[collections]]`".to_string(),
+            };
+
+            ReportsBag::add(report!(
+                kind: ReportKind::Error,
+                message: format!("Unknown collection '{value}'"),
+                labels: {
+                    name.value.span.clone() => "not declared in marston.toml" => Color::BrightRed
+                },
+                notes: [note]
+            ));
+            continue;
+        };
+
+        if let Some(paginate) = list.get_attribute("paginate")
+            && matches!(paginate.value.kind, ValueKind::Boolean(true))
+        {
+            if config.page_size.is_none() {
+                ReportsBag::add(report!(
+                    kind: ReportKind::Error,
+                    message: format!("Collection '{value}' has no page size"),
+                    labels: {
+                        paginate.key.span.clone() => "can't be split into pages" => Color::BrightRed
+                    },
+                    notes: [format!("Set `page_size` in the `[[collections]]` entry of '{value}'")]
+                ));
+            }
+
+            paginated.push(paginate);
+        }
+    }
+
+    if let [first, rest @ ..] = paginated.as_slice()
+        && !rest.is_empty()
+    {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: "More than one paginated list",
+            labels: {
+                first.key.span.clone() => "first paginated list" => Color::BrightYellow
+            },
+            label_vec: rest.iter().map(|attr| (attr.key.span.clone(), "also paginated")).collect_vec() => Color::BrightRed,
+            notes: ["A page can only be split by one list. Use `.limit` for the others"]
+        ));
+    }
+
+    if paginated.is_empty() {
+        for block in info.all_blocks_named(get_or_intern(PAGINATION_BLOCK)) {
+            ReportsBag::add(report!(
+                kind: ReportKind::Warning,
+                message: "Pagination without a paginated list",
+                labels: {
+                    block.name.span.clone() => "never rendered" => Color::BrightYellow
+                },
+                notes: ["Add `.paginate = true` to the `.collection` block the links are for"]
+            ));
+        }
+    }
+}
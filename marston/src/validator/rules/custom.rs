@@ -0,0 +1,382 @@
+use crate::{
+    Span,
+    ast::{Value, ValueKind, ident_table::resolve},
+    config::{RuleConfig, RuleSeverity, RuleValueType},
+    info::Info,
+    report,
+    reports::ReportsBag,
+    validator::{GenericValidator, Label, Report},
+};
+use ariadne::{Color, ReportKind};
+use std::sync::Arc;
+
+/// Runs the rules declared in the `[[rules]]` table of `marston.toml`.
+pub fn validate_custom_rules(info: &mut Info, rules: &[RuleConfig]) {
+    for rule in rules {
+        for validator in compile_rule(rule) {
+            validator.register(info);
+        }
+    }
+}
+
+pub fn compile_rule(rule: &RuleConfig) -> Vec<GenericValidator> {
+    let mut validators = vec![];
+    let shared = Arc::new(rule.clone());
+
+    for attr in &rule.required_attributes {
+        let mut validator = target(
+            rule,
+            GenericValidator::new(attr)
+                .as_attribute()
+                .required()
+                .with_severity(severity(rule.severity)),
+        );
+        if let Some(message) = &rule.message {
+            validator = validator.with_message(message);
+        }
+        validators.push(validator);
+    }
+
+    for attr in &rule.disallowed_attributes {
+        let checked = Arc::clone(&shared);
+        let name = attr.clone();
+        validators.push(target(
+            rule,
+            GenericValidator::new(attr).as_attribute().check_value(move |_, span, ctx| {
+                report_rule_violation(
+                    &checked,
+                    span,
+                    format!("'{name}' should not be used"),
+                    format!("Remove '{name}' from '{}'", resolve(ctx.block.name().key)),
+                );
+            }),
+        ));
+    }
+
+    if let Some(attr) = &rule.attribute {
+        let checked = Arc::clone(&shared);
+        validators.push(target(
+            rule,
+            GenericValidator::new(attr)
+                .as_attribute()
+                .check_value(move |value, span, _| check_attribute_value(&checked, value, span)),
+        ));
+    }
+
+    validators
+}
+
+fn target(rule: &RuleConfig, validator: GenericValidator) -> GenericValidator {
+    match &rule.element {
+        Some(element) if !rule.parent.is_empty() => validator.in_parent(
+            rule.parent.iter().map(String::as_str).chain([element.as_str()]).collect::<Vec<_>>(),
+        ),
+        Some(element) => validator.on_element(element),
+        // `RuleConfig::check` rejects a `parent` without an `element`
+        None => validator.validate_all(),
+    }
+}
+
+/// Checks `type`, `allowed_values` and the length limits of the rule. Values of the wrong type
+/// aren't checked any further.
+fn check_attribute_value(rule: &RuleConfig, value: &Value, span: &Span) {
+    if let Some(expected) = rule.value_type {
+        let (matches, name) = match expected {
+            RuleValueType::String => (matches!(value.kind, ValueKind::String(_)), "a string"),
+            RuleValueType::Number => (matches!(value.kind, ValueKind::Number(_)), "a number"),
+            RuleValueType::Boolean => (matches!(value.kind, ValueKind::Boolean(_)), "a boolean"),
+            RuleValueType::Array => (matches!(value.kind, ValueKind::Array(_)), "an array"),
+        };
+
+        if !matches {
+            report_rule_violation(
+                rule,
+                span,
+                format!("Value must be {name}"),
+                format!("The rule expects {name} here"),
+            );
+            return;
+        }
+    }
+
+    if let Some(allowed) = &rule.allowed_values {
+        // every item of an array has to be allowed
+        let items = match &value.kind {
+            ValueKind::Array(items) => items.iter().collect(),
+            _ => vec![value],
+        };
+
+        for item in items {
+            let text = match &item.kind {
+                ValueKind::String(s) => s.trim().to_string(),
+                other => other.to_string(),
+            };
+
+            if !allowed.contains(&text) {
+                report_rule_violation(
+                    rule,
+                    &item.span,
+                    format!("Value '{text}' is not allowed"),
+                    format!("Allowed values are: {}", allowed.join(", ")),
+                );
+            }
+        }
+    }
+
+    if let Some(s) = value.kind.as_string() {
+        let length = s.trim().chars().count();
+
+        if let Some(min) = rule.min_length
+            && length < min
+        {
+            report_rule_violation(
+                rule,
+                span,
+                format!("String too short (minimum {min} characters)"),
+                format!("Minimum length is {min} characters"),
+            );
+        }
+
+        if let Some(max) = rule.max_length
+            && length > max
+        {
+            report_rule_violation(
+                rule,
+                span,
+                format!("String exceeds {max} characters"),
+                format!("Maximum length is {max} characters"),
+            );
+        }
+    }
+}
+
+fn severity(severity: RuleSeverity) -> ReportKind<'static> {
+    match severity {
+        RuleSeverity::Error => ReportKind::Error,
+        RuleSeverity::Warning => ReportKind::Warning,
+        RuleSeverity::Advice => ReportKind::Advice,
+    }
+}
+
+fn report_rule_violation(rule: &RuleConfig, span: &Span, default_message: String, note: String) {
+    let kind = severity(rule.severity);
+    let color = if kind == ReportKind::Error { Color::BrightRed } else { Color::BrightYellow };
+
+    ReportsBag::add(report!(
+        kind: kind,
+        message: rule.message.clone().unwrap_or(default_message),
+        labels: {
+            span.clone() => "violates a rule from marston.toml" => color
+        },
+        notes: [note]
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        MPath,
+        ast::{Attribute, Block, Interned, MarstonDocument, Node, ident_table::get_or_intern},
+    };
+
+    fn block(
+        id: usize,
+        name: &str,
+        attributes: &[(&str, ValueKind)],
+        children: Vec<Block>,
+    ) -> Block {
+        let mut block = Block::new(Some(Interned::new(get_or_intern(name), 0..0)), id);
+        block.attributes = attributes
+            .iter()
+            .map(|(key, kind)| {
+                Attribute::new(
+                    Interned::new(get_or_intern(key), 0..0),
+                    Value { kind: kind.clone(), span: 0..0 },
+                )
+            })
+            .collect();
+        block.children = children.into_iter().map(Node::Block).collect();
+        block
+    }
+
+    fn string(value: &str) -> ValueKind {
+        ValueKind::String(value.to_string())
+    }
+
+    /// ```text
+    /// body(1) {
+    ///     card(2)[title=Intro]
+    ///     card(3)[title=Outro loading=lazy]
+    ///     nav(4) { item(5)[label=Home style=x] }
+    ///     item(6)[label=About]
+    ///     button(7)[type=sumbit style="color: red"]
+    /// }
+    /// ```
+    fn doc() -> MarstonDocument {
+        MarstonDocument {
+            blocks: vec![block(
+                1,
+                "body",
+                &[],
+                vec![
+                    block(2, "card", &[("title", string("Intro"))], vec![]),
+                    block(
+                        3,
+                        "card",
+                        &[("title", string("Outro")), ("loading", string("lazy"))],
+                        vec![],
+                    ),
+                    block(
+                        4,
+                        "nav",
+                        &[],
+                        vec![block(
+                            5,
+                            "item",
+                            &[("label", string("Home")), ("style", string("x"))],
+                            vec![],
+                        )],
+                    ),
+                    block(6, "item", &[("label", string("About"))], vec![]),
+                    block(
+                        7,
+                        "button",
+                        &[("type", string("sumbit")), ("style", string("color: red"))],
+                        vec![],
+                    ),
+                ],
+            )],
+        }
+    }
+
+    /// Reports of the `[[rules]]` entry written in `rule` on [`doc`].
+    fn reports(rule: &str) -> Vec<String> {
+        let rule = toml::from_str::<RuleConfig>(rule).unwrap();
+        let doc = doc();
+
+        ReportsBag::capture(|| {
+            let mut info = Info::new(Arc::new(MPath::from("index.mr")), MPath::from("."), None);
+            validate_custom_rules(&mut info, &[rule]);
+            std::mem::take(&mut info.dispatcher).run(&doc, &mut info);
+        })
+    }
+
+    #[test]
+    fn compiles_a_validator_per_check() {
+        let rule = toml::from_str::<RuleConfig>(
+            r#"
+            element = "button"
+            required_attributes = ["type", "name"]
+            disallowed_attributes = ["style"]
+            attribute = "type"
+            allowed_values = ["button", "submit", "reset"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(compile_rule(&rule).len(), 4);
+    }
+
+    #[test]
+    fn reports_missing_required_attributes() {
+        let reports = reports(
+            r#"
+            element = "card"
+            required_attributes = ["loading"]
+            "#,
+        );
+
+        assert_eq!(reports.len(), 1, "{reports:?}");
+        assert!(reports[0].contains("Error"), "{}", reports[0]);
+    }
+
+    #[test]
+    fn uses_the_severity_and_message_of_the_rule() {
+        let reports = reports(
+            r#"
+            element = "card"
+            required_attributes = ["loading"]
+            severity = "warning"
+            message = "Cards must declare a loading strategy"
+            "#,
+        );
+
+        assert_eq!(reports.len(), 1, "{reports:?}");
+        assert!(reports[0].contains("Warning"), "{}", reports[0]);
+        assert!(reports[0].contains("Cards must declare a loading strategy"), "{}", reports[0]);
+    }
+
+    #[test]
+    fn rules_without_an_element_check_every_block() {
+        let reports = reports(r#"disallowed_attributes = ["style"]"#);
+
+        assert_eq!(reports.len(), 2, "{reports:?}");
+        assert!(reports.iter().all(|report| report.contains("'style' should not be used")));
+    }
+
+    #[test]
+    fn parent_narrows_the_element() {
+        let reports = reports(
+            r#"
+            element = "item"
+            parent = ["body", "nav"]
+            disallowed_attributes = ["label"]
+            "#,
+        );
+
+        assert_eq!(reports.len(), 1, "{reports:?}");
+    }
+
+    #[test]
+    fn checks_allowed_values() {
+        let reports = reports(
+            r#"
+            element = "button"
+            attribute = "type"
+            allowed_values = ["button", "submit", "reset"]
+            "#,
+        );
+
+        assert_eq!(reports.len(), 1, "{reports:?}");
+        assert!(reports[0].contains("Value 'sumbit' is not allowed"), "{}", reports[0]);
+    }
+
+    #[test]
+    fn values_of_the_wrong_type_are_not_checked_further() {
+        let reports = reports(
+            r#"
+            element = "button"
+            attribute = "type"
+            type = "number"
+            allowed_values = ["1"]
+            "#,
+        );
+
+        assert_eq!(reports.len(), 1, "{reports:?}");
+        assert!(reports[0].contains("Value must be a number"), "{}", reports[0]);
+    }
+
+    #[test]
+    fn checks_string_lengths() {
+        let too_short = reports(
+            r#"
+            element = "card"
+            attribute = "title"
+            min_length = 6
+            "#,
+        );
+        assert_eq!(too_short.len(), 2, "{too_short:?}");
+        assert!(too_short[0].contains("String too short (minimum 6 characters)"));
+
+        let too_long = reports(
+            r#"
+            element = "card"
+            attribute = "title"
+            max_length = 4
+            "#,
+        );
+        assert_eq!(too_long.len(), 2, "{too_long:?}");
+        assert!(too_long[0].contains("String exceeds 4 characters"));
+    }
+}
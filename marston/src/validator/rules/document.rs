@@ -13,11 +13,29 @@ use crate::{
         rules::{
             global_attributes::validate_global_attributes,
             head::{
-                disallowed_style, validate_base, validate_charset, validate_keywords,
-                validate_lang, validate_title, validate_viewport,
+                disallowed_style, validate_base, validate_charset, validate_date,
+                validate_keywords, validate_lang, validate_referrer, validate_title,
+                validate_viewport,
             },
+            ids::validate_id_uniqueness,
             link::validate_link,
+            page::validate_front_matter,
+            performance::{
+                validate_blocking_stylesheets, validate_fetchpriority, validate_image_loading,
+                validate_preload_usage,
+            },
+            root::validate_html_block,
             scripts::validate_script,
+            security::{
+                validate_blank_targets, validate_form_actions, validate_iframe_sandbox,
+                validate_mixed_content, validate_script_urls,
+            },
+            seo::{
+                validate_canonical, validate_description, validate_open_graph, validate_robots,
+                validate_single_h1, validate_sitemap,
+            },
+            social::{validate_social_companions, validate_social_metadata, validate_social_names},
+            structured_data::validate_structured_data,
         },
     },
 };
@@ -31,25 +49,49 @@ impl Validate for MarstonDocument {
         vec![
             ensure_required_tags,
             validate_block_name_uniqueness,
+            validate_html_block,
+            validate_front_matter,
             validate_lang,
             validate_charset,
             validate_title,
             validate_viewport,
             validate_keywords,
+            validate_referrer,
+            validate_date,
             validate_script,
             validate_base,
             validate_link,
             disallowed_style,
             validate_global_attributes,
+            validate_id_uniqueness,
+            validate_blank_targets,
+            validate_script_urls,
+            validate_mixed_content,
+            validate_iframe_sandbox,
+            validate_form_actions,
+            validate_image_loading,
+            validate_blocking_stylesheets,
+            validate_preload_usage,
+            validate_fetchpriority,
+            validate_description,
+            validate_single_h1,
+            validate_canonical,
+            validate_robots,
+            validate_sitemap,
+            validate_open_graph,
+            validate_social_metadata,
+            validate_social_companions,
+            validate_social_names,
+            validate_structured_data,
         ]
     }
 
     fn validate(&self, info: &mut Info) {
         self.call_rules(info);
 
-        for block in &self.blocks {
-            block.validate(info);
-        }
+        // the rules only register their validators, block rules and validators run in one walk
+        let dispatcher = std::mem::take(&mut info.dispatcher);
+        dispatcher.run(self, info);
     }
 }
 
@@ -4,13 +4,20 @@ use crate::{
         Attribute, Block, MarstonDocument, Value, ValueKind,
         ident_table::{get_or_intern, resolve},
     },
-    html::tags::is_unique_tag,
+    html::{
+        head::{HeadMapping, SPECIAL_HEAD_ATTRIBUTES},
+        social::{OPEN_GRAPH, TWITTER},
+        tags::is_unique_tag,
+    },
     info::{BlockInfo, Info},
     report,
     reports::ReportsBag,
+    suggestions::did_you_mean,
     validator::{
         GenericValidator, Validate, ValidationRule,
-        conditions::AttributeEquals, rules::scripts::validate_script, validate_block_no_children,
+        conditions::AttributeEquals,
+        rules::{scripts::validate_script, structured_data::check_date},
+        validate_block_no_children,
     },
 };
 use ariadne::{Color, Label, Report, ReportKind};
@@ -19,7 +26,7 @@ use lasso::Spur;
 use std::{collections::HashMap, fmt::format, sync::Arc};
 use crate::validator::url::{RequiredExtension, UrlValidation};
 
-pub fn validate_title(doc: &MarstonDocument, info: &mut Info) {
+pub fn validate_title(_: &MarstonDocument, info: &mut Info) {
     GenericValidator::new("title")
         .as_attribute()
         .in_parent(vec!["head"])
@@ -39,7 +46,7 @@ pub fn validate_title(doc: &MarstonDocument, info: &mut Info) {
             "new page",
             "title",
         ])
-        .validate(doc, info);
+        .register(info);
 }
 
 pub fn validate_lang(doc: &MarstonDocument, info: &mut Info) {
@@ -47,17 +54,23 @@ pub fn validate_lang(doc: &MarstonDocument, info: &mut Info) {
         return;
     }
 
+    // the language can also be set on the `.html` block
+    let parent = match doc.html_block() {
+        Some(html) if html.get_attribute("lang").is_some() => vec!["html"],
+        _ => vec!["head"],
+    };
+
     GenericValidator::new("lang")
         .as_attribute()
-        .in_parent(vec!["head"])
+        .in_parent(parent)
         .required()
         .must_be_string()
         .string_not_empty()
         .string_valid_language_code()
-        .validate(doc, info);
+        .register(info);
 }
 
-pub fn validate_keywords(doc: &MarstonDocument, info: &mut Info) {
+pub fn validate_keywords(_: &MarstonDocument, info: &mut Info) {
     if (info.no_head) {
         return;
     }
@@ -82,10 +95,10 @@ pub fn validate_keywords(doc: &MarstonDocument, info: &mut Info) {
                 }
             }
         })
-        .validate(doc, info);
+        .register(info);
 }
 
-pub fn validate_charset(doc: &MarstonDocument, info: &mut Info) {
+pub fn validate_charset(_: &MarstonDocument, info: &mut Info) {
     if (info.no_head) {
         return;
     }
@@ -111,10 +124,10 @@ pub fn validate_charset(doc: &MarstonDocument, info: &mut Info) {
                 }
             }
         })
-        .validate(doc, info);
+        .register(info);
 }
 
-pub fn validate_viewport(doc: &MarstonDocument, info: &mut Info) {
+pub fn validate_viewport(_: &MarstonDocument, info: &mut Info) {
     if (info.no_head) {
         return;
     }
@@ -136,9 +149,9 @@ pub fn validate_viewport(doc: &MarstonDocument, info: &mut Info) {
                 ));
             }
         })
-        .validate(doc, info);
+        .register(info);
 }
-pub fn validate_base(doc: &MarstonDocument, info: &mut Info) {
+pub fn validate_base(_: &MarstonDocument, info: &mut Info) {
     if (info.no_head) {
         return;
     }
@@ -148,7 +161,7 @@ pub fn validate_base(doc: &MarstonDocument, info: &mut Info) {
         .in_parent(vec!["head"])
         .block_no_children()
         .require_one_of_attrs(vec!["href", "target"])
-        .validate(doc, info);
+        .register(info);
 
     GenericValidator::new("href")
         .as_attribute()
@@ -161,17 +174,103 @@ pub fn validate_base(doc: &MarstonDocument, info: &mut Info) {
             None
         )))
         .disallowed_chars(vec!['\n', '\t', '<'])
-        .validate(doc, info);
+        .register(info);
 }
 
-pub fn disallowed_style(doc: &MarstonDocument, info: &mut Info) {
+/// With `build.emit_base` the build adds a `<base>` for the base path, which a `.base` block
+/// replaces.
+pub fn validate_emitted_base(_: &MarstonDocument, info: &mut Info) {
+    info.dispatcher.select("head > base", |bases, _| {
+        for base in bases {
+            ReportsBag::add(report!(
+                kind: ReportKind::Warning,
+                message: "'.base' replaces the generated '<base>'",
+                labels: {
+                    base.name().span => "kept instead of the one for `build.base_path`" => Color::BrightYellow
+                },
+                notes: ["Relative URLs resolve against this block instead of the page's directory under the base path. Remove it or turn off `build.emit_base`"]
+            ));
+        }
+    });
+}
+
+pub fn disallowed_style(_: &MarstonDocument, info: &mut Info) {
     if (info.no_head) {
         return;
     }
 
-    GenericValidator::new("style")
-        .as_block()
+    GenericValidator::new("style").as_block().in_parent(vec!["head"]).disallowed().register(info);
+}
+
+/// Publication date of the page, read by feeds and not rendered.
+pub fn validate_date(_: &MarstonDocument, info: &mut Info) {
+    GenericValidator::new("date")
+        .as_attribute()
         .in_parent(vec!["head"])
-        .disallowed()
-        .validate(doc, info);
+        .must_be_string()
+        .check_value(|value, span, _| {
+            if let Some(date) = value.kind.as_string() {
+                check_date(date.trim(), "date", span);
+            }
+        })
+        .register(info);
+}
+
+pub fn validate_referrer(_: &MarstonDocument, info: &mut Info) {
+    GenericValidator::new("referrer")
+        .as_attribute()
+        .in_parent(vec!["head"])
+        .must_be_string()
+        .string_allowed_values(
+            &[
+                "no-referrer",
+                "no-referrer-when-downgrade",
+                "origin",
+                "origin-when-cross-origin",
+                "same-origin",
+                "strict-origin",
+                "strict-origin-when-cross-origin",
+                "unsafe-url",
+            ],
+            true,
+        )
+        .register(info);
+}
+
+/// Attributes without a mapping would be written to `<head>` as is, which isn't valid HTML.
+pub fn validate_unmapped_head_attributes(doc: &MarstonDocument, mappings: &[HeadMapping]) {
+    let Some(head) = doc.find_block_by_name(get_or_intern("head")) else {
+        return;
+    };
+    let known = || {
+        SPECIAL_HEAD_ATTRIBUTES.iter().copied().chain(mappings.iter().map(|m| m.attribute.as_str()))
+    };
+
+    for attr in &head.attributes {
+        let name = resolve(attr.key.key);
+        // unknown social properties are reported by their own rule
+        if known().any(|known| known == name)
+            || name.starts_with(OPEN_GRAPH.prefix)
+            || name.starts_with(TWITTER.prefix)
+        {
+            continue;
+        }
+
+        let note = match did_you_mean(&name, known()) {
+            Some(suggestion) => format!("Did you mean '{suggestion}'?"),
+            None => format!(
+                "Render it as a meta tag with a `[[head]]` entry in marston.toml, e.g. `attribute = \"{name}\"` and `meta = \"{}\"`",
+                name.replace('_', "-")
+            ),
+        };
+
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: format!("Unknown head attribute '{name}'"),
+            labels: {
+                attr.key.span.clone() => "has no HTML equivalent" => Color::BrightRed
+            },
+            notes: [note]
+        ));
+    }
 }
@@ -0,0 +1,204 @@
+use crate::{
+    Span,
+    ast::{Block, MarstonDocument, ident_table::resolve},
+    info::Info,
+    report,
+    reports::ReportsBag,
+    validator::{Label, Report},
+};
+use ariadne::{Color, ReportKind};
+use std::collections::HashMap;
+
+/// Attributes whose value is a single id of an element on the same page.
+const ID_REFERENCE_ATTRIBUTES: &[&str] = &["for", "list", "form"];
+
+/// Attributes whose value is a space separated list of ids. Identifiers can't contain '-', so
+/// `aria-labelledby` is written with an underscore.
+const ID_LIST_REFERENCE_ATTRIBUTES: &[&str] = &["aria_labelledby"];
+
+pub fn validate_id_uniqueness(_: &MarstonDocument, info: &mut Info) {
+    let mut first_seen: HashMap<&str, &Span> = HashMap::new();
+
+    for id in info.ids() {
+        if let Some(orig_span) = first_seen.get(id.value.as_str()) {
+            ReportsBag::add(report!(
+                kind: ReportKind::Error,
+                message: format!("Duplicate id '{}' found", id.value),
+                labels: {
+                    id.span.clone() => format!("id '{}' redefined here", id.value) => Color::BrightRed,
+                    (*orig_span).clone() => format!("id '{}' first defined here", id.value) => Color::Yellow
+                },
+                notes: ["Every id must be unique within the document"]
+            ));
+        } else {
+            first_seen.insert(&id.value, &id.span);
+        }
+    }
+}
+
+pub fn validate_fragment_references(block: &Block, info: &mut Info) {
+    if info.in_structured_data(block) {
+        return;
+    }
+
+    for attr in &block.attributes {
+        let Some(value) = attr.value.kind.as_string() else {
+            continue;
+        };
+        let key = resolve(attr.key.key);
+        // skip the opening quote of the string literal
+        let start = attr.value.span.start + 1;
+
+        if key == "href" {
+            if let Some(fragment) = value.strip_prefix('#') {
+                check_reference(fragment, start + 1..start + value.len(), &key, info);
+            }
+        } else if ID_REFERENCE_ATTRIBUTES.contains(&key.as_str()) {
+            let id = value.trim();
+            let offset = value.len() - value.trim_start().len();
+            check_reference(id, start + offset..start + offset + id.len(), &key, info);
+        } else if ID_LIST_REFERENCE_ATTRIBUTES.contains(&key.as_str()) {
+            let mut offset = 0;
+            for id in value.split(' ') {
+                if !id.is_empty() {
+                    check_reference(id, start + offset..start + offset + id.len(), &key, info);
+                }
+                offset += id.len() + 1;
+            }
+        }
+    }
+}
+
+fn check_reference(id: &str, span: Span, attr_name: &str, info: &Info) {
+    // `href="#"` and `href="#top"` scroll to the top of the page without a target element
+    if id.is_empty() || (attr_name == "href" && id.eq_ignore_ascii_case("top")) {
+        return;
+    }
+
+    if !info.has_id(id) {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: format!("'{}' references id '{}' which does not exist", attr_name, id),
+            labels: {
+                span => format!("no element with id '{}' in this document", id) => Color::BrightRed
+            },
+            notes: [format!("Add `.id = \"{id}\"` to the target block or fix the reference")]
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        MPath,
+        ast::{Attribute, Interned, Node, Value, ValueKind, ident_table::get_or_intern},
+        html::structured_data::JSON_LD_BLOCK,
+        info::InfoWalker,
+    };
+    use std::sync::Arc;
+
+    fn block(id: usize, name: &str, attributes: &[(&str, &str)], children: Vec<Block>) -> Block {
+        let mut block = Block::new(Some(Interned::new(get_or_intern(name), 0..0)), id);
+        block.attributes = attributes
+            .iter()
+            .map(|(key, value)| {
+                Attribute::new(
+                    Interned::new(get_or_intern(key), 0..0),
+                    Value { kind: ValueKind::String((*value).to_string()), span: 0..0 },
+                )
+            })
+            .collect();
+        block.children = children.into_iter().map(Node::Block).collect();
+        block
+    }
+
+    fn check_references(block: &Block, info: &mut Info) {
+        validate_fragment_references(block, info);
+        for child in block.child_blocks() {
+            check_references(child, info);
+        }
+    }
+
+    /// Reports of both id rules on a body with `blocks` in it.
+    fn reports(blocks: Vec<Block>) -> Vec<String> {
+        let mut doc = MarstonDocument { blocks: vec![block(1, "body", &[], blocks)] };
+        let mut info = Info::new(Arc::new(MPath::from("index.mr")), MPath::from("."), None);
+        doc.collect_info(&mut info);
+
+        ReportsBag::capture(|| {
+            validate_id_uniqueness(&doc, &mut info);
+            check_references(&doc.blocks[0], &mut info);
+        })
+    }
+
+    #[test]
+    fn reports_duplicate_ids() {
+        let reports = reports(vec![
+            block(2, "div", &[("id", "team")], vec![]),
+            block(3, "section", &[], vec![block(4, "div", &[("id", " team ")], vec![])]),
+            block(5, "div", &[("id", "about")], vec![]),
+        ]);
+
+        assert_eq!(reports.len(), 1, "{reports:?}");
+        assert!(reports[0].contains("Duplicate id 'team' found"), "{}", reports[0]);
+    }
+
+    #[test]
+    fn structured_data_ids_are_not_element_ids() {
+        let reports = reports(vec![
+            block(2, "div", &[("id", "org")], vec![]),
+            block(
+                3,
+                JSON_LD_BLOCK,
+                &[("id", "org")],
+                vec![block(4, "item", &[("id", "org")], vec![])],
+            ),
+        ]);
+
+        assert!(reports.is_empty(), "{reports:?}");
+    }
+
+    #[test]
+    fn reports_fragment_links_to_missing_ids() {
+        let reports = reports(vec![
+            block(2, "div", &[("id", "team")], vec![]),
+            block(3, "a", &[("href", "#team")], vec![]),
+            block(4, "a", &[("href", "#missing")], vec![]),
+            block(5, "a", &[("href", "#")], vec![]),
+            block(6, "a", &[("href", "#top")], vec![]),
+        ]);
+
+        assert_eq!(reports.len(), 1, "{reports:?}");
+        assert!(
+            reports[0].contains("'href' references id 'missing' which does not exist"),
+            "{}",
+            reports[0]
+        );
+    }
+
+    #[test]
+    fn reports_id_references_to_missing_ids() {
+        let reports = reports(vec![
+            block(2, "input", &[("id", "name"), ("list", "names")], vec![]),
+            block(3, "label", &[("for", " name ")], vec![]),
+            block(4, "form", &[("aria_labelledby", "name  title")], vec![]),
+        ]);
+
+        assert_eq!(reports.len(), 2, "{reports:?}");
+        assert!(reports[0].contains("'list' references id 'names'"), "{}", reports[0]);
+        assert!(reports[1].contains("'aria_labelledby' references id 'title'"), "{}", reports[1]);
+    }
+
+    #[test]
+    fn links_inside_structured_data_are_not_checked() {
+        let reports = reports(vec![block(
+            2,
+            JSON_LD_BLOCK,
+            &[("href", "#org")],
+            vec![block(3, "item", &[("href", "#missing")], vec![])],
+        )]);
+
+        assert!(reports.is_empty(), "{reports:?}");
+    }
+}
@@ -6,7 +6,7 @@ use crate::{
     reports::ReportsBag,
     validator::{
         GenericValidator, Label, Report,
-        conditions::{AttributeEquals, ConditionResult},
+        conditions::{AttributeIn, HasAttribute},
         rules::blocking::blocking_attribute,
         url::{RequiredExtension, UrlValidation},
     },
@@ -14,7 +14,7 @@ use crate::{
 use ariadne::{Color, ReportKind};
 use mime::Mime;
 
-pub fn validate_link(doc: &MarstonDocument, info: &mut Info) {
+pub fn validate_link(_: &MarstonDocument, info: &mut Info) {
     GenericValidator::new("rel")
         .as_attribute()
         .must_be_string()
@@ -49,7 +49,7 @@ pub fn validate_link(doc: &MarstonDocument, info: &mut Info) {
             ],
             false,
         )
-        .validate(doc, info);
+        .register(info);
 
     GenericValidator::new("href")
         .in_parent(vec!["head", "link"])
@@ -58,25 +58,12 @@ pub fn validate_link(doc: &MarstonDocument, info: &mut Info) {
         .must_be_string()
         .string_not_empty()
         .string_valid_url(Some(UrlValidation::new(
-            &[
-                "javascript", "data", "vbscript"
-            ],
+            &["javascript", "data", "vbscript"],
             true,
-            Some(RequiredExtension::new(
-                "css",
-                Box::new(AttributeEquals::new(|block| {
-                    if let Some(attr) = block.get_attribute("rel") {
-                        if let Some(string) = attr.value.kind.as_string() {
-                            return ConditionResult::new(string == "stylesheet", Some("link elements with rel=\"stylesheet\" must have a file extension of .css"))
-                        }
-                    }
-
-                    ConditionResult::new(false, None)
-                })),
-            )),
+            Some(RequiredExtension::new("css", Box::new(AttributeIn::new("rel", &["stylesheet"])))),
         )))
         .string_prefer_https()
-        .validate(doc, info);
+        .register(info);
 
     GenericValidator::new("type")
         .in_parent(vec!["head", "link"])
@@ -96,27 +83,14 @@ pub fn validate_link(doc: &MarstonDocument, info: &mut Info) {
                 ));
             }
         })
-        .validate(doc, info);
+        .register(info);
 
     GenericValidator::new("as")
         .in_parent(vec!["head", "link"])
         .as_attribute()
         .must_be_string()
         .string_not_empty()
-        .required_if(AttributeEquals::new(|block| {
-            if let Some(rel) = block.get_attribute("rel")
-                && let Some(string) = rel.value.kind.as_string()
-            {
-                if string == "preload" {
-                    return ConditionResult::new(
-                        true,
-                        Some("preload elements must have an as attribute"),
-                    );
-                }
-            }
-
-            ConditionResult::new(false, None)
-        }))
+        .required_if(AttributeIn::new("rel", &["preload"]))
         .string_allowed_values(
             &[
                 "audio", "document", "embed", "fetch", "font", "image", "object", "script",
@@ -124,48 +98,18 @@ pub fn validate_link(doc: &MarstonDocument, info: &mut Info) {
             ],
             true,
         )
-        .validate(doc, info);
+        .register(info);
 
     blocking_attribute()
         .in_parent(vec!["head", "link"])
-        .valid_if(AttributeEquals::new(|block| {
-            if let Some(rel) = block.get_attribute("rel")
-                && let Some(string) = rel.value.kind.as_string()
-            {
-                if string == "expect" || string == "stylesheet" {
-                    return ConditionResult::new(
-                        true,
-                        None
-                    );
-                }
-            }
-
-            ConditionResult::new(false, Some("'blocking' attribute is only allowed on link elements with rel=\"preload\" or rel=\"stylesheet\""))
-        }))
-        .validate(doc, info);
-
-    GenericValidator::new("fetchpriority")
-        .must_be_string()
-        .string_not_empty()
-        .as_attribute()
-        .in_parent(vec!["head", "link"])
-        .string_allowed_values(&["high", "low", "medium"], true)
-        .validate(doc, info);
+        .valid_if(AttributeIn::new("rel", &["expect", "stylesheet"]))
+        .register(info);
 
     GenericValidator::new("hreflang")
         .as_attribute()
-        .valid_if(AttributeEquals::new(|block| {
-            if let None = block.get_attribute("href") {
-                return ConditionResult::new(
-                    false,
-                    Some(
-                        "'hreflang' attribute is only valid on link if 'href' attribute is present",
-                    ),
-                );
-            }
-
-            ConditionResult::new(true, None)
-        }))
+        .in_parent(vec!["head", "link"])
+        .must_be_string()
+        .valid_if(HasAttribute::new("href"))
         .string_valid_language_code()
-        .validate(doc, info);
+        .register(info);
 }
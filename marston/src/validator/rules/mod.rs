@@ -1,7 +1,19 @@
+mod assets;
 mod block;
 mod blocking;
+pub mod collection;
+pub mod custom;
 pub mod document;
 mod global_attributes;
-mod head;
+pub mod head;
+mod ids;
 mod link;
+mod obsolete;
+mod page;
+mod performance;
+mod root;
 mod scripts;
+mod security;
+mod seo;
+mod social;
+mod structured_data;
@@ -0,0 +1,125 @@
+use crate::{
+    Span,
+    ast::{Block, ident_table::resolve},
+    info::Info,
+    report,
+    reports::ReportsBag,
+    validator::{Label, Report},
+};
+use ariadne::{Color, ReportKind};
+
+/// Elements the HTML standard lists as obsolete, with what to use instead.
+/// See https://html.spec.whatwg.org/multipage/obsolete.html#non-conforming-features
+const OBSOLETE_ELEMENTS: &[(&str, &str)] = &[
+    ("acronym", "Use 'abbr' instead"),
+    ("applet", "Use 'object' or 'embed' instead"),
+    ("basefont", "Set the font with CSS instead"),
+    ("bgsound", "Use 'audio' instead"),
+    ("big", "Use CSS `font-size` instead"),
+    ("blink", "Use CSS animations instead"),
+    ("center", "Use CSS `text-align: center` or `margin: auto` instead"),
+    ("dir", "Use 'ul' instead"),
+    ("font", "Set the font with CSS instead"),
+    ("frame", "Use 'iframe' instead"),
+    ("frameset", "Use 'iframe' or a CSS layout instead"),
+    ("isindex", "Use a 'form' with an 'input' instead"),
+    ("keygen", "Use the Web Crypto API instead"),
+    ("listing", "Use 'pre' and 'code' instead"),
+    ("marquee", "Use CSS animations instead"),
+    ("menuitem", "Use 'button' instead"),
+    ("multicol", "Use CSS `columns` instead"),
+    ("nextid", "Use unique ids instead"),
+    ("nobr", "Use CSS `white-space: nowrap` instead"),
+    ("noembed", "Put the fallback content inside 'object' instead"),
+    ("noframes", "Remove it, frames are not supported anymore"),
+    ("plaintext", "Serve the file as text/plain instead"),
+    ("spacer", "Use CSS `margin` or `padding` instead"),
+    ("strike", "Use 'del' for removed text or 's' for text that is no longer accurate"),
+    ("tt", "Use 'code', 'kbd' or 'samp' instead"),
+    ("xmp", "Use 'pre' and 'code' instead"),
+];
+
+/// Presentational attributes that were replaced by CSS, with the elements they are obsolete on.
+/// An empty list means the attribute is obsolete on every element.
+const OBSOLETE_ATTRIBUTES: &[(&str, &[&str], &str)] = &[
+    ("align", &[], "Use CSS `text-align`, `float` or `margin` instead"),
+    ("bgcolor", &[], "Use CSS `background-color` instead"),
+    ("background", &[], "Use CSS `background-image` instead"),
+    ("border", &["table", "img", "object"], "Use CSS `border` instead"),
+    ("cellpadding", &["table"], "Use CSS `padding` on the cells instead"),
+    ("cellspacing", &["table"], "Use CSS `border-spacing` instead"),
+    ("frame", &["table"], "Use CSS `border` instead"),
+    ("rules", &["table"], "Use CSS `border` on the cells instead"),
+    ("summary", &["table"], "Describe the table in a 'caption' instead"),
+    ("width", &["table", "col", "colgroup", "td", "th", "hr", "pre"], "Use CSS `width` instead"),
+    ("height", &["table", "tr", "td", "th"], "Use CSS `height` instead"),
+    ("valign", &[], "Use CSS `vertical-align` instead"),
+    ("nowrap", &["td", "th"], "Use CSS `white-space: nowrap` instead"),
+    ("char", &[], "Remove it, no browser supports it"),
+    ("charoff", &[], "Remove it, no browser supports it"),
+    ("axis", &["td", "th"], "Use the 'scope' attribute on 'th' instead"),
+    ("abbr", &["td"], "Use a 'th' with 'abbr' or put the short form in the cell instead"),
+    ("text", &["body"], "Use CSS `color` instead"),
+    ("link", &["body"], "Use CSS `color` on `a:link` instead"),
+    ("vlink", &["body"], "Use CSS `color` on `a:visited` instead"),
+    ("alink", &["body"], "Use CSS `color` on `a:active` instead"),
+    ("marginwidth", &["body", "iframe"], "Use CSS `margin` instead"),
+    ("marginheight", &["body", "iframe"], "Use CSS `margin` instead"),
+    ("hspace", &["img", "object", "iframe"], "Use CSS `margin` instead"),
+    ("vspace", &["img", "object", "iframe"], "Use CSS `margin` instead"),
+    ("clear", &["br"], "Use CSS `clear` instead"),
+    ("noshade", &["hr"], "Use CSS `border` and `background` instead"),
+    ("size", &["hr"], "Use CSS `height` instead"),
+    ("compact", &["dl", "ol", "ul", "menu", "dir"], "Use CSS `margin` and `padding` instead"),
+    ("type", &["ul", "li"], "Use CSS `list-style-type` instead"),
+    ("frameborder", &["iframe"], "Use CSS `border` instead"),
+    ("scrolling", &["iframe"], "Use CSS `overflow` instead"),
+    (
+        "longdesc",
+        &["img", "iframe"],
+        "Link to the description with 'a' or describe the image with 'aria-describedby' instead",
+    ),
+    ("name", &["img"], "Use 'id' instead"),
+    ("charset", &["a", "link", "script"], "Remove it, documents are always UTF-8"),
+    ("language", &["script"], "Remove it or use 'type' for modules"),
+    ("rev", &["a", "link"], "Use 'rel' with the opposite keyword instead"),
+    ("version", &["html"], "Remove it"),
+];
+
+pub fn validate_obsolete(block: &Block, _: &mut Info) {
+    let Some(name) = &block.name else {
+        return;
+    };
+    let element = resolve(name.key);
+
+    if let Some((_, replacement)) =
+        OBSOLETE_ELEMENTS.iter().find(|(obsolete, _)| *obsolete == element)
+    {
+        ReportsBag::add(report!(
+            kind: ReportKind::Warning,
+            message: format!("'{element}' is obsolete"),
+            labels: {
+                name.span.clone() => "obsolete element" => Color::BrightYellow
+            },
+            notes: [format!("'{element}' was removed from HTML and browsers may render it differently or not at all. {replacement}")]
+        ));
+    }
+
+    for attr in &block.attributes {
+        let key = resolve(attr.key.key);
+        let obsolete = OBSOLETE_ATTRIBUTES.iter().find(|(obsolete, elements, _)| {
+            *obsolete == key && (elements.is_empty() || elements.contains(&element.as_str()))
+        });
+
+        if let Some((_, _, replacement)) = obsolete {
+            ReportsBag::add(report!(
+                kind: ReportKind::Warning,
+                message: format!("'{key}' is obsolete on '{element}'"),
+                labels: {
+                    attr.key.span.clone() => "obsolete attribute" => Color::BrightYellow
+                },
+                notes: [*replacement]
+            ));
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{
+    Span,
+    ast::{
+        MarstonDocument, ValueKind,
+        ident_table::{get_or_intern, resolve},
+    },
+    info::Info,
+    report,
+    reports::ReportsBag,
+    site::front_matter::{PAGE_ATTRIBUTES, PAGE_BLOCK, permalink_problem},
+    suggestions::did_you_mean,
+    validator::{
+        GenericValidator, Label, Report, rules::structured_data::check_date,
+        validate_block_no_children,
+    },
+};
+use ariadne::{Color, ReportKind};
+use itertools::Itertools;
+
+/// `.page` holds the build metadata of the document. It comes first, once, and only has
+/// attributes.
+pub fn validate_front_matter(doc: &MarstonDocument, info: &mut Info) {
+    GenericValidator::new("permalink")
+        .as_attribute()
+        .in_parent(vec![PAGE_BLOCK])
+        .must_be_string()
+        .string_not_empty()
+        .check_value(|value, span, _| {
+            if let Some(permalink) = value.kind.as_string() {
+                check_permalink(permalink.trim(), span);
+            }
+        })
+        .register(info);
+
+    GenericValidator::new("layout")
+        .as_attribute()
+        .in_parent(vec![PAGE_BLOCK])
+        .must_be_string()
+        .string_not_empty()
+        .check_value(|_, span, _| {
+            ReportsBag::add(report!(
+                kind: ReportKind::Warning,
+                message: "'layout' has no effect yet",
+                labels: {
+                    span.clone() => "not applied to the page" => Color::BrightYellow
+                },
+                notes: ["Layouts are reserved for a later version, the page is written as it is"]
+            ));
+        })
+        .register(info);
+
+    GenericValidator::new("date")
+        .as_attribute()
+        .in_parent(vec![PAGE_BLOCK])
+        .must_be_string()
+        .check_value(|value, span, _| {
+            if let Some(date) = value.kind.as_string() {
+                check_date(date.trim(), "date", span);
+            }
+        })
+        .register(info);
+
+    GenericValidator::new("tags")
+        .as_attribute()
+        .in_parent(vec![PAGE_BLOCK])
+        .must_be_array(Some(ValueKind::dummy_string()))
+        .register(info);
+
+    GenericValidator::new("priority")
+        .as_attribute()
+        .in_parent(vec![PAGE_BLOCK])
+        .must_be_number()
+        .number_min(0.0)
+        .number_max(1.0)
+        .with_note("The sitemap priority ranges from 0.0 to 1.0, pages default to 0.5")
+        .register(info);
+
+    for flag in ["pretty_url", "draft", "noindex"] {
+        GenericValidator::new(flag)
+            .as_attribute()
+            .in_parent(vec![PAGE_BLOCK])
+            .must_be_boolean()
+            .register(info);
+    }
+
+    info.dispatcher.select("* page", |blocks, _| {
+        for block in blocks {
+            ReportsBag::add(report!(
+                kind: ReportKind::Error,
+                message: "'page' block inside another block",
+                labels: {
+                    block.name().span => "only allowed at the top of the file" => Color::BrightRed
+                },
+                notes: ["'.page' holds the build metadata of the whole document. Move it to the top of the file"]
+            ));
+        }
+    });
+
+    let pages = doc.blocks.iter().filter(|block| block.is_front_matter()).collect_vec();
+    let Some(page) = pages.first() else {
+        return;
+    };
+
+    if let [_, rest @ ..] = pages.as_slice()
+        && !rest.is_empty()
+    {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: "More than one 'page' block",
+            labels: {
+                page.name().span => "first declared here" => Color::BrightYellow
+            },
+            label_vec: rest.iter().map(|block| (block.name().span, "declared again")).collect_vec() => Color::BrightRed,
+            notes: ["Merge the attributes into the first '.page' block"]
+        ));
+    }
+
+    if let Some(first) = doc.blocks.first()
+        && !first.is_front_matter()
+    {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: "'page' block after other blocks",
+            labels: {
+                page.name().span => "must be the first block of the file" => Color::BrightRed,
+                first.name().span => "comes before it" => Color::BrightYellow
+            },
+            notes: ["Move '.page' to the top of the file, like front matter"]
+        ));
+    }
+
+    validate_block_no_children(page, PAGE_BLOCK);
+
+    for attr in &page.attributes {
+        let name = resolve(attr.key.key);
+        if PAGE_ATTRIBUTES.contains(&name.as_str()) {
+            continue;
+        }
+
+        let note = match did_you_mean(&name, PAGE_ATTRIBUTES.iter().copied()) {
+            Some(suggestion) => format!("Did you mean '{suggestion}'?"),
+            None => format!("'.page' supports {}", PAGE_ATTRIBUTES.join(", ")),
+        };
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: format!("Unknown page attribute '{name}'"),
+            labels: {
+                attr.key.span.clone() => "not build metadata" => Color::BrightRed
+            },
+            notes: [note]
+        ));
+    }
+
+    if let Some(date) = page.get_attribute("date")
+        && let Some(head) = doc.find_block_by_name(get_or_intern("head"))
+        && let Some(head_date) = head.get_attribute("date")
+    {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: "'date' is set twice",
+            labels: {
+                date.key.span.clone() => "set on the page block" => Color::BrightRed,
+                head_date.key.span.clone() => "and on the head block" => Color::BrightYellow
+            },
+            notes: ["Both set when the page was published. Keep only one of them"]
+        ));
+    }
+}
+
+fn check_permalink(permalink: &str, span: &Span) {
+    if let Some(problem) = permalink_problem(permalink) {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: format!("Invalid permalink '{permalink}'"),
+            labels: {
+                span.clone() => problem => Color::BrightRed
+            },
+            notes: ["Permalinks are paths from the root of the site, e.g. \"/about/\" or \"/blog/hello.html\""]
+        ));
+    }
+}
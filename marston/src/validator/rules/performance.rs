@@ -0,0 +1,276 @@
+use crate::{
+    Span,
+    ast::{Block, MarstonDocument, ident_table::resolve},
+    info::Info,
+    report,
+    reports::ReportsBag,
+    validator::{GenericValidator, Label, Report, rules::assets::resolve_local_reference},
+};
+use ariadne::{Color, ReportKind};
+use itertools::Itertools;
+use std::collections::HashSet;
+
+/// Images at the start of the body that are assumed to be visible without scrolling.
+const ABOVE_THE_FOLD_IMAGES: usize = 2;
+
+/// Stylesheets that can block the first render before the page is reported.
+const MAX_BLOCKING_STYLESHEETS: usize = 3;
+
+/// Preload destinations whose target is referenced from the page itself. Fonts and fetches are
+/// used from CSS and scripts, so they can't be checked.
+const CHECKED_PRELOADS: &[&str] = &["script", "style", "image"];
+
+/// Blocks that reserve their space in the layout from `width` and `height`.
+const SIZED_ELEMENTS: &[&str] = &["img", "iframe"];
+
+/// Called for every script in head by `validate_script`. Inline scripts don't block on a
+/// download and are left alone.
+pub fn validate_render_blocking_script(script: &Block) {
+    let Some(src) = script.get_attribute("src") else {
+        return;
+    };
+
+    let is_module = script
+        .get_attribute("type")
+        .and_then(|attr| attr.value.kind.as_string())
+        .is_some_and(|value| value.trim() == "module");
+
+    if is_module || has_flag(script, "async") || has_flag(script, "defer") {
+        return;
+    }
+
+    ReportsBag::add(report!(
+        kind: ReportKind::Warning,
+        message: "Render-blocking script in head",
+        labels: {
+            src.value.span.clone() => "downloaded and run before the page is rendered" => Color::BrightYellow
+        },
+        notes: ["The parser stops until the script is fetched and executed. Add `.defer = true`, or `.async = true` for scripts that don't depend on the document"]
+    ));
+}
+
+pub fn validate_layout_shift(block: &Block, _: &mut Info) {
+    let Some(name) = &block.name else {
+        return;
+    };
+    let name = resolve(name.key);
+
+    if !SIZED_ELEMENTS.contains(&name.as_str()) {
+        return;
+    }
+
+    let missing = ["width", "height"]
+        .into_iter()
+        .filter(|attr| block.get_attribute(attr).is_none())
+        .collect_vec();
+    if missing.is_empty() {
+        return;
+    }
+
+    ReportsBag::add(report!(
+        kind: ReportKind::Warning,
+        message: format!("'{name}' without {}", missing.iter().map(|attr| format!("'{attr}'")).join(" and ")),
+        labels: {
+            block.name().span => "its size is unknown until it loads" => Color::BrightYellow
+        },
+        notes: ["The browser can't reserve space for it, so the content below jumps once it loads (Cumulative Layout Shift). Set `.width` and `.height` to its intrinsic size"]
+    ));
+}
+
+/// The first images are likely visible on load and shouldn't wait, the rest should be lazy.
+pub fn validate_image_loading(_: &MarstonDocument, info: &mut Info) {
+    info.dispatcher.select("body img", |images, _| check_image_loading(images));
+}
+
+fn check_image_loading(images: &[&Block]) {
+    for (index, image) in images.iter().enumerate() {
+        let loading = image.get_attribute("loading");
+        let is_lazy = loading
+            .and_then(|attr| attr.value.kind.as_string())
+            .is_some_and(|value| value.trim().eq_ignore_ascii_case("lazy"));
+
+        if index < ABOVE_THE_FOLD_IMAGES {
+            if let Some(loading) = loading
+                && is_lazy
+            {
+                ReportsBag::add(report!(
+                    kind: ReportKind::Warning,
+                    message: "Lazy loading an image that is likely above the fold",
+                    labels: {
+                        loading.value.span.clone() => "delays one of the first images on the page" => Color::BrightYellow
+                    },
+                    notes: [format!("The first {ABOVE_THE_FOLD_IMAGES} images are assumed to be visible on load, lazy loading them delays the Largest Contentful Paint. Remove `.loading`")]
+                ));
+            }
+        } else if !is_lazy {
+            ReportsBag::add(report!(
+                kind: ReportKind::Warning,
+                message: "Image below the fold is not lazy loaded",
+                labels: {
+                    image.name().span => "downloaded before it is scrolled into view" => Color::BrightYellow
+                },
+                notes: ["It competes for bandwidth with the content that is visible on load. Add `.loading = \"lazy\"`"]
+            ));
+        }
+    }
+}
+
+pub fn validate_blocking_stylesheets(_: &MarstonDocument, info: &mut Info) {
+    info.dispatcher
+        .select("head > link[rel~=stylesheet]", |links, _| check_blocking_stylesheets(links));
+}
+
+fn check_blocking_stylesheets(links: &[&Block]) {
+    let blocking = links
+        .iter()
+        .filter(|link| {
+            // print and other non-matching media don't block rendering
+            link.get_attribute("media")
+                .and_then(|attr| attr.value.kind.as_string())
+                .is_none_or(|media| matches!(media.trim(), "" | "all" | "screen"))
+        })
+        .collect_vec();
+
+    if blocking.len() <= MAX_BLOCKING_STYLESHEETS {
+        return;
+    }
+
+    ReportsBag::add(report!(
+        kind: ReportKind::Warning,
+        message: format!("{} render-blocking stylesheets in head", blocking.len()),
+        labels: {
+            blocking[0].name().span => "first stylesheet" => Color::BrightYellow
+        },
+        label_vec: blocking.iter().skip(MAX_BLOCKING_STYLESHEETS).map(|link| (link.name().span, "blocks the first render as well")).collect_vec() => Color::Yellow,
+        notes: [format!("Every stylesheet has to be downloaded before anything is rendered. Combine them or load non-critical styles later, keep at most {MAX_BLOCKING_STYLESHEETS}")]
+    ));
+}
+
+/// Preloaded resources that nothing on the page uses are downloaded for nothing.
+pub fn validate_preload_usage(_: &MarstonDocument, info: &mut Info) {
+    info.dispatcher.select("[src], [href], [srcset], [poster], [data]", check_preload_usage);
+}
+
+/// Gets every block with a reference, the preloads among them are told apart by
+/// [`is_preload`].
+fn check_preload_usage(blocks: &[&Block], info: &Info) {
+    let (preloads, referencing): (Vec<&Block>, Vec<&Block>) =
+        blocks.iter().partition(|block| is_preload(block, info));
+    if preloads.is_empty() {
+        return;
+    }
+
+    let used = referencing
+        .into_iter()
+        .flat_map(|block| block.attributes.iter())
+        .filter(|attr| {
+            matches!(resolve(attr.key.key).as_str(), "src" | "href" | "srcset" | "poster" | "data")
+        })
+        .filter_map(|attr| attr.value.kind.as_string())
+        .flat_map(|value| {
+            value.split(',').filter_map(|candidate| candidate.split_whitespace().next())
+        })
+        .map(|value| reference_key(value, info))
+        .collect::<HashSet<_>>();
+
+    for preload in preloads {
+        let destination = preload.get_attribute("as").and_then(|attr| attr.value.kind.as_string());
+        let Some(href) = preload.get_attribute("href") else {
+            continue;
+        };
+        let Some(value) = href.value.kind.as_string() else {
+            continue;
+        };
+
+        if destination.is_some_and(|d| CHECKED_PRELOADS.contains(&d.trim()))
+            && !used.contains(&reference_key(value, info))
+        {
+            report_unused_preload(value.trim(), &href.value.span);
+        }
+    }
+}
+
+/// Whether the block is a `head > link[rel~=preload]`.
+fn is_preload(block: &Block, info: &Info) -> bool {
+    block.name.as_ref().is_some_and(|name| resolve(name.key) == "link")
+        && info.parent_of(block).is_some_and(|parent| resolve(parent.name.key) == "head")
+        && block
+            .get_attribute("rel")
+            .and_then(|attr| attr.value.kind.as_string())
+            .is_some_and(|rel| rel.split_whitespace().any(|token| token == "preload"))
+}
+
+fn report_unused_preload(href: &str, span: &Span) {
+    ReportsBag::add(report!(
+        kind: ReportKind::Warning,
+        message: format!("Preloaded '{href}' is not used on this page"),
+        labels: {
+            span.clone() => "downloaded with high priority but never referenced" => Color::BrightYellow
+        },
+        notes: ["Preloads compete with the resources the page needs first. Remove the preload or reference the resource from the page"]
+    ));
+}
+
+pub fn validate_fetchpriority(_: &MarstonDocument, info: &mut Info) {
+    GenericValidator::new("fetchpriority")
+        .as_attribute()
+        .in_parent("img, link, script, iframe")
+        .must_be_string()
+        .string_allowed_values(&["high", "low", "auto"], true)
+        .check_value(|value, span, ctx| {
+            let is_high = value.kind.as_string().is_some_and(|v| v.trim().eq_ignore_ascii_case("high"));
+            let is_lazy = ctx
+                .block
+                .get_attribute("loading")
+                .and_then(|attr| attr.value.kind.as_string())
+                .is_some_and(|v| v.trim().eq_ignore_ascii_case("lazy"));
+
+            if is_high && is_lazy {
+                ReportsBag::add(report!(
+                    kind: ReportKind::Warning,
+                    message: "'fetchpriority=\"high\"' on a lazy loaded image",
+                    labels: {
+                        span.clone() => "the image isn't requested until it is near the viewport" => Color::BrightYellow
+                    },
+                    notes: ["Lazy loading defeats the priority hint. Remove `.loading = \"lazy\"` from important images"]
+                ));
+            }
+        })
+        .register(info);
+
+    GenericValidator::new("fetchpriority")
+        .as_attribute()
+        .in_parent(":not(img, link, script, iframe)")
+        .check_value(|_, span, _| {
+            ReportsBag::add(report!(
+                kind: ReportKind::Warning,
+                message: "'fetchpriority' has no effect here",
+                labels: {
+                    span.clone() => "ignored by the browser" => Color::BrightYellow
+                },
+                notes: ["Only 'img', 'link', 'script' and 'iframe' support priority hints"]
+            ));
+        })
+        .register(info);
+
+    info.dispatcher.select("body img[fetchpriority=high]", |high, _| {
+        if high.len() > 1 {
+            ReportsBag::add(report!(
+                kind: ReportKind::Warning,
+                message: format!("{} images with 'fetchpriority=\"high\"'", high.len()),
+                label_vec: high.iter().map(|image| (image.name().span, "prioritized")).collect_vec() => Color::BrightYellow,
+                notes: ["When everything is prioritized nothing is. Keep the hint for the image that is the Largest Contentful Paint"]
+            ));
+        }
+    });
+}
+
+fn has_flag(block: &Block, name: &str) -> bool {
+    block.get_attribute(name).is_some_and(|attr| attr.value.kind.as_boolean() != Some(false))
+}
+
+/// Identifies a referenced resource regardless of how the path to it is written.
+fn reference_key(value: &str, info: &Info) -> String {
+    resolve_local_reference(value, info)
+        .map_or_else(|| value.trim().to_string(), |path| path.to_string())
+}
@@ -0,0 +1,90 @@
+use crate::{
+    Span,
+    ast::{MarstonDocument, Node, ident_table::resolve},
+    info::Info,
+    report,
+    reports::ReportsBag,
+    validator::{GenericValidator, Label, Report},
+};
+use ariadne::{Color, ReportKind};
+
+/// `.html` stands for the `<html>` element, so it can only wrap the head and the body.
+pub fn validate_html_block(doc: &MarstonDocument, info: &mut Info) {
+    GenericValidator::new("dir")
+        .as_attribute()
+        .in_parent(vec!["html"])
+        .must_be_string()
+        .string_allowed_values(&["ltr", "rtl", "auto"], true)
+        .register(info);
+
+    info.dispatcher.select("* html", |blocks, _| {
+        for block in blocks {
+            ReportsBag::add(report!(
+                kind: ReportKind::Error,
+                message: "'html' block inside another block",
+                labels: {
+                    block.name().span => "only allowed at the top level" => Color::BrightRed
+                },
+                notes: ["The '.html' block stands for the root element. Move it to the top level and put '.head' and '.body' inside it"]
+            ));
+        }
+    });
+
+    let Some(html) = doc.html_block() else {
+        return;
+    };
+
+    for block in
+        doc.blocks.iter().filter(|block| !block.is_root_element() && !block.is_front_matter())
+    {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: format!("'{}' block next to the 'html' block", resolve(block.name().key)),
+            labels: {
+                block.name().span => "outside of the html block" => Color::BrightRed,
+                html.name().span => "wraps the document" => Color::BrightBlue
+            },
+            notes: ["When the document has an '.html' block, '.head' and '.body' go inside it. Only '.page' stays next to it"]
+        ));
+    }
+
+    if html.children.iter().any(|child| matches!(child, Node::Text(_))) {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: "Text in the 'html' block",
+            labels: {
+                html.name().span => "contains text" => Color::BrightRed
+            },
+            notes: ["Text belongs in '.body'"]
+        ));
+    }
+
+    for block in html.child_blocks() {
+        let name = resolve(block.name().key);
+        if name != "head" && name != "body" {
+            ReportsBag::add(report!(
+                kind: ReportKind::Error,
+                message: format!("'{name}' block inside the 'html' block"),
+                labels: {
+                    block.name().span => "only '.head' and '.body' are allowed here" => Color::BrightRed
+                },
+                notes: [format!("Move '.{name}' into '.head' or '.body'")]
+            ));
+        }
+    }
+
+    if let Some(lang) = html.get_attribute("lang")
+        && let Some(head) = html.child_blocks().find(|block| resolve(block.name().key) == "head")
+        && let Some(head_lang) = head.get_attribute("lang")
+    {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: "'lang' is set twice",
+            labels: {
+                lang.key.span.clone() => "set on the html block" => Color::BrightRed,
+                head_lang.key.span.clone() => "and on the head block" => Color::BrightYellow
+            },
+            notes: ["Both end up on the '<html>' element. Keep only one of them"]
+        ));
+    }
+}
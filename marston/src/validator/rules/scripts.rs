@@ -3,7 +3,8 @@ use crate::{
     ast::{Block, MarstonDocument, ident_table::get_or_intern},
     info::Info,
     validator::{
-        GenericValidator, validate_block_no_children, validate_block_not_empty,
+        GenericValidator, rules::performance::validate_render_blocking_script,
+        validate_block_no_children, validate_block_not_empty,
         validate_mutually_exclusive_attributes,
     },
 };
@@ -26,6 +27,8 @@ pub fn validate_script(doc: &MarstonDocument, info: &mut Info) {
                     "Consider using only one loading strategy for clarity",
                 ],
             );
+
+            validate_render_blocking_script(script_block);
         }
     }
 
@@ -37,19 +40,19 @@ pub fn validate_script(doc: &MarstonDocument, info: &mut Info) {
         .string_valid_url(None)
         .string_file_extension(".js")
         .string_prefer_https()
-        .validate(doc, info);
+        .register(info);
 
     GenericValidator::new("async")
         .in_parent(vec!["head", "script"])
         .as_attribute()
         .must_be_boolean()
-        .validate(doc, info);
+        .register(info);
 
     GenericValidator::new("defer")
         .in_parent(vec!["head", "script"])
         .as_attribute()
         .must_be_boolean()
-        .validate(doc, info);
+        .register(info);
 
     GenericValidator::new("type")
         .in_parent(vec!["head", "script"])
@@ -57,5 +60,5 @@ pub fn validate_script(doc: &MarstonDocument, info: &mut Info) {
         .must_be_string()
         .string_not_empty()
         .string_allowed_values(&["text/javascript", "application/javascript", "module"], false)
-        .validate(doc, info);
+        .register(info);
 }
@@ -0,0 +1,174 @@
+use crate::{
+    Span,
+    ast::{Block, MarstonDocument, ident_table::resolve},
+    info::Info,
+    report,
+    reports::ReportsBag,
+    validator::{GenericValidator, Label, Report},
+};
+use ariadne::{Color, ReportKind};
+use url::Url;
+
+/// Blocks and attributes that load a subresource, which browsers block or warn about when it's
+/// requested over plain HTTP from an HTTPS page.
+const SUBRESOURCE_ATTRIBUTES: &[(&str, &str)] = &[
+    ("src", "img, script, iframe, audio, video, source, track, embed, input"),
+    ("srcset", "img, source"),
+    ("poster", "video"),
+    ("data", "object"),
+    (
+        "href",
+        "link[rel~=stylesheet], link[rel~=icon], link[rel~=preload], link[rel~=modulepreload], link[rel~=manifest]",
+    ),
+];
+
+pub fn validate_blank_targets(_: &MarstonDocument, info: &mut Info) {
+    GenericValidator::new("target")
+        .as_attribute()
+        .in_parent("a, area, form")
+        .check_value(|value, span, ctx| {
+            let opens_new_context =
+                value.kind.as_string().is_some_and(|s| s.trim().eq_ignore_ascii_case("_blank"));
+            let rel = ctx.block.get_attribute("rel").and_then(|rel| rel.value.kind.as_string());
+            let isolated = rel.is_some_and(|rel| {
+                rel.split_whitespace()
+                    .any(|v| v.eq_ignore_ascii_case("noopener") || v.eq_ignore_ascii_case("noreferrer"))
+            });
+
+            if opens_new_context && !isolated {
+                ReportsBag::add(report!(
+                    kind: ReportKind::Warning,
+                    message: "'target=\"_blank\"' without 'rel=\"noopener\"'",
+                    labels: {
+                        span.clone() => "opens the link in a new browsing context" => Color::BrightYellow
+                    },
+                    notes: ["The opened page can reach this one through `window.opener` and redirect it to a phishing page. Add `.rel = \"noopener\"`"]
+                ));
+            }
+        })
+        .register(info);
+}
+
+/// Inline handlers such as `onclick` only run if the Content Security Policy allows
+/// `'unsafe-inline'` and are an easy place for injected markup to execute code.
+pub fn validate_event_handlers(block: &Block, _: &mut Info) {
+    for attr in &block.attributes {
+        let name = resolve(attr.key.key);
+        let is_handler = name.strip_prefix("on").is_some_and(|event| {
+            !event.is_empty() && event.chars().all(|c| c.is_ascii_lowercase())
+        });
+
+        if is_handler {
+            ReportsBag::add(report!(
+                kind: ReportKind::Warning,
+                message: format!("Inline event handler '{name}'"),
+                labels: {
+                    attr.key.span.clone() => "runs inline script" => Color::BrightYellow
+                },
+                notes: ["Inline handlers are blocked by a Content Security Policy without 'unsafe-inline'. Attach the listener with `addEventListener` from a script file instead"]
+            ));
+        }
+    }
+}
+
+/// Only the scheme is checked, whether the rest is a valid URL is up to the rules of the block.
+pub fn validate_script_urls(_: &MarstonDocument, info: &mut Info) {
+    for (attr, parent) in [("href", "body [href]"), ("src", "body [src]")] {
+        GenericValidator::new(attr)
+            .as_attribute()
+            .in_parent(parent)
+            .check_value(|value, span, _| {
+                let Some(url) = value.kind.as_string().and_then(|s| Url::parse(s.trim()).ok())
+                else {
+                    return;
+                };
+
+                if matches!(url.scheme(), "javascript" | "vbscript") {
+                    ReportsBag::add(report!(
+                        kind: ReportKind::Error,
+                        message: format!("Found disallowed URL protocol: {}.", url.scheme()),
+                        labels: {
+                            span.clone() => "runs script when followed" => Color::BrightRed
+                        },
+                        notes: ["Script URLs run code when followed, are blocked by a Content Security Policy and are a common XSS vector. Attach an event listener from a script file instead"]
+                    ));
+                }
+            })
+            .register(info);
+    }
+}
+
+pub fn validate_mixed_content(_: &MarstonDocument, info: &mut Info) {
+    if !info.is_https() {
+        return;
+    }
+
+    for (attr, parent) in SUBRESOURCE_ATTRIBUTES {
+        GenericValidator::new(attr)
+            .as_attribute()
+            .in_parent(*parent)
+            .check_value(|value, span, _| {
+                let Some(s) = value.kind.as_string() else {
+                    return;
+                };
+
+                // srcset holds comma separated candidates, the url comes first in each
+                for candidate in s.split(',') {
+                    let url = candidate.split_whitespace().next().unwrap_or_default();
+                    if is_insecure(url) {
+                        report_mixed_content(url, span);
+                    }
+                }
+            })
+            .register(info);
+    }
+}
+
+fn report_mixed_content(url: &str, span: &Span) {
+    ReportsBag::add(report!(
+        kind: ReportKind::Error,
+        message: format!("Mixed content: '{url}' is loaded over HTTP"),
+        labels: {
+            span.clone() => "insecure resource on an HTTPS page" => Color::BrightRed
+        },
+        notes: ["The site is served over HTTPS according to `project.base_url`. Browsers block insecure scripts, frames and stylesheets and flag the page as not secure, use an https:// URL"]
+    ));
+}
+
+pub fn validate_iframe_sandbox(_: &MarstonDocument, info: &mut Info) {
+    GenericValidator::new("sandbox")
+        .as_attribute()
+        .on_element("iframe")
+        .required()
+        .with_severity(ReportKind::Warning)
+        .with_message("'iframe' without 'sandbox'")
+        .with_note("Without `sandbox` the embedded page can run scripts, submit forms and open popups. Add `.sandbox = \"\"` and allow only the capabilities it needs, e.g. \"allow-scripts\"")
+        .register(info);
+}
+
+pub fn validate_form_actions(_: &MarstonDocument, info: &mut Info) {
+    for (attr, parent) in [("action", "form"), ("formaction", "button, input")] {
+        GenericValidator::new(attr)
+            .as_attribute()
+            .in_parent(parent)
+            .check_value(|value, span, _| {
+                if let Some(s) = value.kind.as_string()
+                    && is_insecure(s.trim())
+                {
+                    ReportsBag::add(report!(
+                        kind: ReportKind::Warning,
+                        message: "Form data is sent over HTTP",
+                        labels: {
+                            span.clone() => "submits to an insecure URL" => Color::BrightYellow
+                        },
+                        notes: ["Everything the user enters, including passwords, is sent unencrypted and browsers warn before submitting it. Use an https:// URL"]
+                    ));
+                }
+            })
+            .register(info);
+    }
+}
+
+fn is_insecure(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| url.scheme() == "http")
+}
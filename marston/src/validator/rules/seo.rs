@@ -0,0 +1,382 @@
+use crate::{
+    Span,
+    ast::{
+        Block, MarstonDocument, Value,
+        ident_table::{get_or_intern, resolve},
+    },
+    html::social::OPEN_GRAPH,
+    info::Info,
+    report,
+    reports::ReportsBag,
+    site::sitemap::{CHANGEFREQ_VALUES, SITEMAP_BLOCK},
+    suggestions::did_you_mean,
+    validator::{GenericValidator, Label, Report, rules::structured_data::check_date},
+};
+use ariadne::{Color, ReportKind};
+use itertools::Itertools;
+use url::Url;
+
+/// Directives understood by the major search engines, see
+/// https://developers.google.com/search/docs/crawling-indexing/robots-meta-tag
+const ROBOTS_DIRECTIVES: &[&str] = &[
+    "all",
+    "index",
+    "noindex",
+    "follow",
+    "nofollow",
+    "none",
+    "noarchive",
+    "nocache",
+    "nosnippet",
+    "noimageindex",
+    "notranslate",
+    "indexifembedded",
+];
+
+/// Directives that take a value after a colon, e.g. `max-snippet:50`.
+const ROBOTS_DIRECTIVES_WITH_VALUE: &[&str] =
+    &["max-snippet", "max-image-preview", "max-video-preview", "unavailable_after"];
+
+/// Pairs of directives that contradict each other.
+const CONFLICTING_ROBOTS_DIRECTIVES: &[(&str, &str)] = &[
+    ("index", "noindex"),
+    ("follow", "nofollow"),
+    ("all", "noindex"),
+    ("all", "nofollow"),
+    ("all", "none"),
+    ("none", "index"),
+    ("none", "follow"),
+];
+
+/// Properties every Open Graph object has to define.
+const REQUIRED_OPEN_GRAPH_PROPERTIES: &[&str] = &["og:title", "og:type", "og:image", "og:url"];
+
+/// A missing description is only reported for sites with `project.base_url`, the ones that are
+/// set up to be published.
+pub fn validate_description(_: &MarstonDocument, info: &mut Info) {
+    if info.no_head {
+        return;
+    }
+
+    let mut validator = GenericValidator::new("description").as_attribute().in_parent(vec!["head"]);
+    if info.base_url.is_some() {
+        validator = validator
+            .required()
+            .with_severity(ReportKind::Warning)
+            .with_note("Search engines show the description below the title in results. Add `.description` with a summary of the page");
+    }
+
+    validator
+        .must_be_string()
+        .string_not_empty()
+        .string_min_length(50)
+        .string_max_length(160)
+        .register(info);
+}
+
+pub fn validate_single_h1(doc: &MarstonDocument, info: &mut Info) {
+    let Some(body) = doc.find_block_by_name(get_or_intern("body")) else {
+        return;
+    };
+    let body = body.name().span;
+
+    info.dispatcher.select("body h1", move |headings, _| match headings {
+        [] => ReportsBag::add(report!(
+            kind: ReportKind::Warning,
+            message: "Page has no 'h1'",
+            labels: {
+                body.clone() => "no top-level heading in here" => Color::BrightYellow
+            },
+            notes: ["Search engines and screen readers use the 'h1' to tell what the page is about. Add one heading that describes the page"]
+        )),
+        [_] => {}
+        [first, rest @ ..] => ReportsBag::add(report!(
+            kind: ReportKind::Warning,
+            message: format!("Page has {} 'h1' headings", headings.len()),
+            labels: {
+                first.name().span => "first 'h1'" => Color::BrightYellow
+            },
+            label_vec: rest.iter().map(|h1| (h1.name().span, "another 'h1'")).collect_vec() => Color::Yellow,
+            notes: ["Keep a single 'h1' for the page and use 'h2' to 'h6' for the sections in it"]
+        )),
+    });
+}
+
+pub fn validate_canonical(doc: &MarstonDocument, info: &mut Info) {
+    let Some(head) = doc.find_block_by_name(get_or_intern("head")) else {
+        return;
+    };
+
+    // `.canonical` on head is rendered as a canonical link as well
+    let attribute = head
+        .get_attribute("canonical")
+        .map(|attr| (attr.key.span.clone(), Some(attr.value.clone())));
+    let head = head.name().span;
+
+    info.dispatcher.select("head > link[rel~=canonical]", move |links, info| {
+        let canonicals = attribute
+            .iter()
+            .cloned()
+            .chain(links.iter().map(|link| {
+                (link.name().span, link.get_attribute("href").map(|href| href.value.clone()))
+            }))
+            .collect_vec();
+        check_canonicals(&canonicals, &head, info);
+    });
+}
+
+fn check_canonicals(canonicals: &[(Span, Option<Value>)], head: &Span, info: &Info) {
+    let Some(((span, href), duplicates)) = canonicals.split_first() else {
+        // sites without `project.base_url` aren't published under a known URL yet
+        if info.base_url.is_none() {
+            return;
+        }
+
+        ReportsBag::add(report!(
+            kind: ReportKind::Warning,
+            message: "Page has no canonical URL",
+            labels: {
+                head.clone() => "no canonical URL in here" => Color::BrightYellow
+            },
+            notes: ["Without it search engines pick one of the URLs the page is reachable under themselves. Add `.canonical = \"https://...\"`"]
+        ));
+        return;
+    };
+
+    if !duplicates.is_empty() {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: "Page has more than one canonical URL",
+            labels: {
+                span.clone() => "first canonical URL" => Color::BrightRed
+            },
+            label_vec: duplicates.iter().map(|(span, _)| (span.clone(), "another canonical URL")).collect_vec() => Color::Yellow,
+            notes: ["Search engines ignore all of them when there is more than one. Keep one"]
+        ));
+    }
+
+    if let Some(href) = href
+        && let Some(value) = href.kind.as_string()
+    {
+        check_canonical_url(value.trim(), &href.span, info);
+    }
+}
+
+fn check_canonical_url(value: &str, span: &Span, info: &Info) {
+    let url = match Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => url,
+        _ => {
+            ReportsBag::add(report!(
+                kind: ReportKind::Warning,
+                message: "Canonical URL should be absolute",
+                labels: {
+                    span.clone() => "not an absolute http(s) URL" => Color::BrightYellow
+                },
+                notes: ["Relative canonical URLs are resolved differently by each crawler. Use the full URL, e.g. 'https://example.com/page.html'"]
+            ));
+            return;
+        }
+    };
+
+    if url.fragment().is_some() {
+        ReportsBag::add(report!(
+            kind: ReportKind::Warning,
+            message: "Canonical URL contains a fragment",
+            labels: {
+                span.clone() => "fragments are ignored by search engines" => Color::BrightYellow
+            },
+            notes: ["Remove the '#...' part"]
+        ));
+    }
+
+    if let Some(base_url) = &info.base_url
+        && base_url.host_str() != url.host_str()
+    {
+        ReportsBag::add(report!(
+            kind: ReportKind::Warning,
+            message: "Canonical URL points to another host",
+            labels: {
+                span.clone() => format!("expected a URL on '{}'", base_url.host_str().unwrap_or_default()) => Color::BrightYellow
+            },
+            notes: ["The host differs from `project.base_url`, so search engines will index the other site instead of this page"]
+        ));
+    }
+}
+
+pub fn validate_robots(_: &MarstonDocument, info: &mut Info) {
+    GenericValidator::new("robots")
+        .as_attribute()
+        .in_parent(vec!["head"])
+        .must_be_string()
+        .string_not_empty()
+        .check_value(|value, span, _| check_robots(value.kind.as_string(), span))
+        .register(info);
+
+    GenericValidator::new("content")
+        .as_attribute()
+        .in_parent("head > meta[name=robots]")
+        .must_be_string()
+        .check_value(|value, span, _| check_robots(value.kind.as_string(), span))
+        .register(info);
+}
+
+pub fn validate_sitemap(_: &MarstonDocument, info: &mut Info) {
+    GenericValidator::new(SITEMAP_BLOCK)
+        .as_block()
+        .in_parent(vec!["head"])
+        .block_no_children()
+        .register(info);
+
+    GenericValidator::new("changefreq")
+        .as_attribute()
+        .in_parent(vec!["head", SITEMAP_BLOCK])
+        .must_be_string()
+        .string_allowed_values(CHANGEFREQ_VALUES, true)
+        .register(info);
+
+    GenericValidator::new("priority")
+        .as_attribute()
+        .in_parent(vec!["head", SITEMAP_BLOCK])
+        .must_be_number()
+        .number_min(0.0)
+        .number_max(1.0)
+        .register(info);
+
+    GenericValidator::new("lastmod")
+        .as_attribute()
+        .in_parent(vec!["head", SITEMAP_BLOCK])
+        .must_be_string()
+        .check_value(|value, span, _| {
+            if let Some(date) = value.kind.as_string() {
+                check_date(date.trim(), "lastmod", span);
+            }
+        })
+        .register(info);
+}
+
+fn check_robots(value: Option<&String>, span: &Span) {
+    let Some(value) = value else {
+        return;
+    };
+    let directives = value
+        .split(',')
+        .map(|d| d.trim().to_ascii_lowercase())
+        .filter(|d| !d.is_empty())
+        .collect_vec();
+
+    for directive in &directives {
+        let known = ROBOTS_DIRECTIVES.contains(&directive.as_str())
+            || directive
+                .split_once(':')
+                .is_some_and(|(name, _)| ROBOTS_DIRECTIVES_WITH_VALUE.contains(&name.trim()));
+        if known {
+            continue;
+        }
+
+        let candidates = ROBOTS_DIRECTIVES.iter().chain(ROBOTS_DIRECTIVES_WITH_VALUE).copied();
+        let note = match did_you_mean(directive, candidates) {
+            Some(suggestion) => format!("Did you mean '{suggestion}'?"),
+            None => format!("Known directives are: {}", ROBOTS_DIRECTIVES.join(", ")),
+        };
+
+        ReportsBag::add(report!(
+            kind: ReportKind::Warning,
+            message: format!("Unknown robots directive '{directive}'"),
+            labels: {
+                span.clone() => "ignored by search engines" => Color::BrightYellow
+            },
+            notes: [note]
+        ));
+    }
+
+    for (a, b) in CONFLICTING_ROBOTS_DIRECTIVES {
+        if directives.iter().any(|d| d == a) && directives.iter().any(|d| d == b) {
+            ReportsBag::add(report!(
+                kind: ReportKind::Warning,
+                message: format!("Conflicting robots directives '{a}' and '{b}'"),
+                labels: {
+                    span.clone() => "contradicting directives" => Color::BrightYellow
+                },
+                notes: ["Search engines apply the most restrictive directive. Remove the one that doesn't apply"]
+            ));
+        }
+    }
+}
+
+/// Once a page uses Open Graph, it needs the required properties to render a preview.
+pub fn validate_open_graph(doc: &MarstonDocument, info: &mut Info) {
+    let Some(head) = doc.find_block_by_name(get_or_intern("head")) else {
+        return;
+    };
+
+    let from_head = head_open_graph_properties(head);
+    info.dispatcher.select("head > meta[property^='og:']", move |metas, _| {
+        let mut defined = vec![];
+        for meta in metas {
+            if let Some(property) =
+                meta.get_attribute("property").and_then(|p| p.value.kind.as_string())
+            {
+                defined.push((property.trim().to_string(), meta.name().span));
+            }
+        }
+        defined.extend(from_head.iter().cloned());
+
+        check_open_graph(&defined);
+    });
+}
+
+/// Properties defined by the attributes of head, with the spans that define them.
+fn head_open_graph_properties(head: &Block) -> Vec<(String, Span)> {
+    let mut defined = vec![];
+
+    // `.og_*` attributes and the `.og` block are rendered with defaults for the title and type
+    let mut attributes = head
+        .attributes
+        .iter()
+        .filter_map(|attr| {
+            let name = resolve(attr.key.key);
+            Some((name.strip_prefix(OPEN_GRAPH.prefix)?.to_string(), attr.key.span.clone()))
+        })
+        .collect_vec();
+    for block in head.find_all_child_blocks(get_or_intern(OPEN_GRAPH.block)) {
+        attributes.push((String::new(), block.name().span));
+        attributes
+            .extend(block.attributes.iter().map(|a| (resolve(a.key.key), a.key.span.clone())));
+    }
+    if let Some((_, span)) = attributes.first() {
+        defined.push(("og:type".to_string(), span.clone()));
+        if head.get_attribute("title").is_some() {
+            defined.push(("og:title".to_string(), span.clone()));
+        }
+    }
+    defined.extend(
+        attributes
+            .into_iter()
+            .filter_map(|(name, span)| Some((OPEN_GRAPH.property(&name)?.to_string(), span))),
+    );
+
+    defined
+}
+
+fn check_open_graph(defined: &[(String, Span)]) {
+    let Some((_, first)) = defined.first() else {
+        return;
+    };
+    let missing = REQUIRED_OPEN_GRAPH_PROPERTIES
+        .iter()
+        .filter(|p| !defined.iter().any(|(property, _)| property == *p))
+        .collect_vec();
+
+    if missing.is_empty() {
+        return;
+    }
+
+    ReportsBag::add(report!(
+        kind: ReportKind::Warning,
+        message: format!("Incomplete Open Graph metadata, missing {}", missing.iter().map(|p| format!("'{p}'")).join(", ")),
+        labels: {
+            first.clone() => "Open Graph metadata starts here" => Color::BrightYellow
+        },
+        notes: [format!("Link previews need all of {}. Add the missing properties, e.g. `.og_image = \"https://...\"` to 'head'", REQUIRED_OPEN_GRAPH_PROPERTIES.join(", "))]
+    ));
+}
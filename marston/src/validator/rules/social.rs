@@ -0,0 +1,212 @@
+use crate::{
+    Span,
+    ast::{
+        MarstonDocument, Value,
+        ident_table::{get_or_intern, resolve},
+    },
+    html::social::{OPEN_GRAPH, OPEN_GRAPH_TYPES, SocialVocabulary, TWITTER, TWITTER_CARDS},
+    info::Info,
+    report,
+    reports::ReportsBag,
+    suggestions::did_you_mean,
+    validator::{
+        GenericValidator, Label, Report,
+        conditions::{AttributeIn, HasAttribute, ValidationContext},
+    },
+};
+use ariadne::{Color, ReportKind};
+use url::Url;
+
+/// Properties holding a URL that social networks fetch from their own servers.
+const OPEN_GRAPH_URLS: &[&str] = &["url", "image", "image_secure_url", "audio", "video"];
+const TWITTER_URLS: &[&str] = &["image", "player", "player_stream"];
+
+pub fn validate_social_metadata(_: &MarstonDocument, info: &mut Info) {
+    for vocabulary in [&OPEN_GRAPH, &TWITTER] {
+        GenericValidator::new(vocabulary.block)
+            .as_block()
+            .in_parent(vec!["head"])
+            .block_no_children()
+            .register(info);
+    }
+
+    for (parent, prefix) in [("head", OPEN_GRAPH.prefix), ("head > og", "")] {
+        for attr in OPEN_GRAPH_URLS {
+            GenericValidator::new(&format!("{prefix}{attr}"))
+                .as_attribute()
+                .in_parent(parent)
+                .must_be_string()
+                .string_not_empty()
+                .check_value(check_absolute_url)
+                .register(info);
+        }
+
+        GenericValidator::new(&format!("{prefix}type"))
+            .as_attribute()
+            .in_parent(parent)
+            .must_be_string()
+            .string_allowed_values(OPEN_GRAPH_TYPES, true)
+            .register(info);
+
+        for attr in ["image_width", "image_height"] {
+            GenericValidator::new(&format!("{prefix}{attr}"))
+                .as_attribute()
+                .in_parent(parent)
+                .must_be_number()
+                .number_positive()
+                .register(info);
+        }
+    }
+
+    for (parent, prefix) in [("head", TWITTER.prefix), ("head > twitter", "")] {
+        for attr in TWITTER_URLS {
+            GenericValidator::new(&format!("{prefix}{attr}"))
+                .as_attribute()
+                .in_parent(parent)
+                .must_be_string()
+                .string_not_empty()
+                .check_value(check_absolute_url)
+                .register(info);
+        }
+
+        GenericValidator::new(&format!("{prefix}card"))
+            .as_attribute()
+            .in_parent(parent)
+            .must_be_string()
+            .string_allowed_values(TWITTER_CARDS, true)
+            .register(info);
+
+        for attr in ["site", "creator"] {
+            GenericValidator::new(&format!("{prefix}{attr}"))
+                .as_attribute()
+                .in_parent(parent)
+                .must_be_string()
+                .check_value(check_handle)
+                .register(info);
+        }
+
+        for attr in ["player_width", "player_height"] {
+            GenericValidator::new(&format!("{prefix}{attr}"))
+                .as_attribute()
+                .in_parent(parent)
+                .must_be_number()
+                .number_positive()
+                .register(info);
+        }
+    }
+}
+
+/// Fields that only make sense together with another one.
+pub fn validate_social_companions(_: &MarstonDocument, info: &mut Info) {
+    for (parent, image, alt) in [
+        ("head", "og_image", "og_image_alt"),
+        ("head > og", "image", "image_alt"),
+        ("head", "twitter_image", "twitter_image_alt"),
+        ("head > twitter", "image", "image_alt"),
+    ] {
+        GenericValidator::new(alt)
+            .as_attribute()
+            .in_parent(parent)
+            .required_if(HasAttribute::new(image))
+            .with_severity(ReportKind::Warning)
+            .with_note("Screen readers announce the preview image with its alternative text")
+            .register(info);
+    }
+
+    for (parent, card, fields) in [
+        (
+            "head",
+            "twitter_card",
+            ["twitter_player", "twitter_player_width", "twitter_player_height"],
+        ),
+        ("head > twitter", "card", ["player", "player_width", "player_height"]),
+    ] {
+        for field in fields {
+            GenericValidator::new(field)
+                .as_attribute()
+                .in_parent(parent)
+                .required_if(AttributeIn::new(card, &["player"]))
+                .with_note("Player cards embed the player in an iframe of the given size")
+                .register(info);
+        }
+    }
+}
+
+/// Unknown properties would end up as attributes of `<head>` or as an unknown element.
+pub fn validate_social_names(doc: &MarstonDocument, _: &mut Info) {
+    let Some(head) = doc.find_block_by_name(get_or_intern("head")) else {
+        return;
+    };
+
+    for vocabulary in [&OPEN_GRAPH, &TWITTER] {
+        for attr in &head.attributes {
+            let name = resolve(attr.key.key);
+            if let Some(property) = name.strip_prefix(vocabulary.prefix) {
+                check_property(vocabulary, property, vocabulary.prefix, &attr.key.span);
+            }
+        }
+
+        for block in head.find_all_child_blocks(get_or_intern(vocabulary.block)) {
+            for attr in &block.attributes {
+                check_property(vocabulary, &resolve(attr.key.key), "", &attr.key.span);
+            }
+        }
+    }
+}
+
+fn check_property(vocabulary: &SocialVocabulary, property: &str, prefix: &str, span: &Span) {
+    if vocabulary.property(property).is_some() {
+        return;
+    }
+
+    let note = match did_you_mean(property, vocabulary.names()) {
+        Some(suggestion) => format!("Did you mean '{prefix}{suggestion}'?"),
+        None => format!(
+            "Known properties are: {}",
+            vocabulary.names().map(|name| format!("{prefix}{name}")).collect::<Vec<_>>().join(", ")
+        ),
+    };
+
+    ReportsBag::add(report!(
+        kind: ReportKind::Error,
+        message: format!("Unknown {} property '{prefix}{property}'", vocabulary.label),
+        labels: {
+            span.clone() => "not a known property" => Color::BrightRed
+        },
+        notes: [note]
+    ));
+}
+
+fn check_absolute_url(value: &Value, span: &Span, _: &ValidationContext) {
+    let Some(s) = value.kind.as_string() else {
+        return;
+    };
+
+    if !Url::parse(s.trim()).is_ok_and(|url| matches!(url.scheme(), "http" | "https")) {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: "Social metadata URLs must be absolute",
+            labels: {
+                span.clone() => "not an absolute http(s) URL" => Color::BrightRed
+            },
+            notes: ["Social networks fetch it from their own servers and can't resolve relative paths. Use the full URL, e.g. 'https://example.com/cover.png'"]
+        ));
+    }
+}
+
+fn check_handle(value: &Value, span: &Span, _: &ValidationContext) {
+    let Some(s) = value.kind.as_string() else {
+        return;
+    };
+
+    if !s.trim().starts_with('@') {
+        ReportsBag::add(report!(
+            kind: ReportKind::Warning,
+            message: format!("'{}' is not a username", s.trim()),
+            labels: {
+                span.clone() => "expected an '@username'" => Color::BrightYellow
+            },
+            notes: ["Twitter Card accounts are written with a leading '@'"]
+        ));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{
+    Span,
+    ast::{Block, MarstonDocument, Node, ident_table::resolve},
+    html::structured_data::JSON_LD_BLOCK,
+    info::Info,
+    report,
+    reports::ReportsBag,
+    site::date::DateTime,
+    suggestions::did_you_mean,
+    validator::{GenericValidator, Label, Report},
+};
+use ariadne::{Color, ReportKind};
+use itertools::Itertools;
+
+/// Properties search engines need before they show a rich result for the type.
+const REQUIRED_PROPERTIES: &[(&str, &[&str])] = &[
+    ("Organization", &["name", "url"]),
+    ("Person", &["name"]),
+    ("Article", &["headline", "author", "date_published"]),
+    ("NewsArticle", &["headline", "author", "date_published"]),
+    ("BlogPosting", &["headline", "author", "date_published"]),
+    ("BreadcrumbList", &["item_list_element"]),
+    ("ListItem", &["position", "name"]),
+    ("Product", &["name"]),
+    ("Offer", &["price", "price_currency"]),
+];
+
+/// A product is only shown as a rich result with at least one of these.
+const PRODUCT_DETAILS: &[&str] = &["offers", "review", "aggregate_rating"];
+
+/// Properties holding an ISO 8601 date.
+const DATE_PROPERTIES: &[&str] = &[
+    "date_created",
+    "date_modified",
+    "date_published",
+    "upload_date",
+    "founding_date",
+    "birth_date",
+    "price_valid_until",
+    "valid_from",
+    "valid_through",
+];
+
+pub fn validate_structured_data(_: &MarstonDocument, info: &mut Info) {
+    GenericValidator::new("type")
+        .as_attribute()
+        .in_parent(JSON_LD_BLOCK)
+        .required()
+        .with_note("Set the schema.org type of the data, e.g. `.type = \"Organization\"`")
+        .must_be_string()
+        .string_not_empty()
+        .register(info);
+
+    info.dispatcher.select(JSON_LD_BLOCK, |blocks, _| {
+        for block in blocks {
+            check_item(block);
+        }
+    });
+}
+
+fn check_item(block: &Block) {
+    if block.children.iter().any(|child| matches!(child, Node::Text(_))) {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: "Text in structured data",
+            labels: {
+                block.name().span => "contains text" => Color::BrightRed
+            },
+            notes: ["Structured data is made of attributes and blocks only. Put the text in an attribute, e.g. `.description = \"...\"`"]
+        ));
+    }
+
+    for attr in &block.attributes {
+        let name = resolve(attr.key.key);
+        if DATE_PROPERTIES.contains(&name.as_str())
+            && let Some(value) = attr.value.kind.as_string()
+        {
+            check_date(value.trim(), &name, &attr.value.span);
+        }
+    }
+
+    if let Some(ty) = block.get_attribute("type")
+        && let Some(value) = ty.value.kind.as_string()
+    {
+        check_required_properties(block, value.trim(), &ty.value.span);
+    }
+
+    for child in block.child_blocks() {
+        check_item(child);
+    }
+}
+
+fn check_required_properties(block: &Block, ty: &str, span: &Span) {
+    let Some((_, required)) = REQUIRED_PROPERTIES.iter().find(|(name, _)| *name == ty) else {
+        // other schema.org types are fine, but a near miss is most likely a typo
+        if let Some(suggestion) =
+            did_you_mean(ty, REQUIRED_PROPERTIES.iter().map(|(name, _)| *name))
+        {
+            ReportsBag::add(report!(
+                kind: ReportKind::Warning,
+                message: format!("Unknown schema.org type '{ty}'"),
+                labels: {
+                    span.clone() => "not a type search engines understand" => Color::BrightYellow
+                },
+                notes: [format!("Did you mean '{suggestion}'?")]
+            ));
+        }
+        return;
+    };
+
+    let missing = required.iter().filter(|name| !has_property(block, name)).collect_vec();
+    if !missing.is_empty() {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: format!("'{ty}' is missing {}", missing.iter().map(|name| format!("'{name}'")).join(", ")),
+            labels: {
+                span.clone() => format!("requires {}", required.iter().map(|name| format!("'{name}'")).join(", ")) => Color::BrightRed
+            },
+            notes: [format!("Search engines ignore '{ty}' data without these properties. Add them as attributes or blocks, e.g. `.{} = ...`", missing[0])]
+        ));
+    }
+
+    if ty == "Product" && !PRODUCT_DETAILS.iter().any(|name| has_property(block, name)) {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: "'Product' needs offers, a review or a rating",
+            labels: {
+                span.clone() => format!("requires one of {}", PRODUCT_DETAILS.iter().map(|name| format!("'{name}'")).join(", ")) => Color::BrightRed
+            },
+            notes: ["Add an `.offers(.type = \"Offer\", ...)` block with the price"]
+        ));
+    }
+}
+
+fn has_property(block: &Block, name: &str) -> bool {
+    block.get_attribute(name).is_some()
+        || block.child_blocks().any(|child| resolve(child.name().key) == name)
+}
+
+pub fn check_date(value: &str, name: &str, span: &Span) {
+    if DateTime::parse(value).is_none() {
+        ReportsBag::add(report!(
+            kind: ReportKind::Error,
+            message: format!("'{name}' is not an ISO 8601 date"),
+            labels: {
+                span.clone() => "expected a date like \"2024-05-31\" or \"2024-05-31T08:00:00+02:00\"" => Color::BrightRed
+            },
+            notes: ["Search engines only understand dates in ISO 8601 format"]
+        ));
+    }
+}
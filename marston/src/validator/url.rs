@@ -1,7 +1,5 @@
 use crate::{
-    Span,
-    ast::Block,
-    report,
+    Span, report,
     reports::ReportsBag,
     validator::{
         GenericValidator, Label, Report,
@@ -9,7 +7,7 @@ use crate::{
     },
 };
 use ariadne::{Color, ReportKind};
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use url::Url;
 
 #[derive(Default)]
@@ -42,18 +40,23 @@ impl RequiredExtension {
 }
 
 impl GenericValidator {
-    fn validate_extension(path: &str, options: &UrlValidation, span: Span, block: &Block) {
+    fn validate_extension(
+        path: &str,
+        options: &UrlValidation,
+        span: Span,
+        ctx: &ValidationContext,
+    ) {
         if let Some(ext) = &options.required_extension {
             let extension = ext.extension;
 
-            let result = ext.condition.evaluate(&block.into());
+            let result = ext.condition.evaluate(ctx);
             if result.result {
                 let file_extension = Path::new(path).extension().and_then(|e| e.to_str());
 
                 if file_extension != Some(extension) {
                     ReportsBag::add(report!(
                         kind: ReportKind::Error,
-                        message: format!("URL should have '.{}' extension. {}", extension, result.messages.first().unwrap()),
+                        message: format!("URL should have '.{extension}' extension when {}", result.describe()),
                         labels: {
                             span.clone() => "invalid URL extension" => Color::BrightRed
                         },
@@ -63,10 +66,11 @@ impl GenericValidator {
         }
     }
 
+    #[must_use]
     pub fn string_valid_url(self, options: Option<UrlValidation>) -> Self {
         let options = options.unwrap_or_default();
 
-        self.check_value(move |value, span, block| {
+        self.check_value(move |value, span, ctx| {
             if let Some(s) = value.kind.as_string() {
                 let trimmed = s.trim();
 
@@ -79,20 +83,16 @@ impl GenericValidator {
                                 labels: {
                                     span.clone() => "disallowed invalid URL" => Color::BrightRed
                                 },
+                                notes: [protocol_note(url.scheme())]
                             ));
                         }
 
-                        Self::validate_extension(url.path(), &options, span.clone(), block);
+                        Self::validate_extension(url.path(), &options, span.clone(), ctx);
                     }
                     Err(err) => {
                         if options.allow_paths {
-                            match trimmed.parse::<PathBuf>() {
-                                Ok(path) => {
-                                    Self::validate_extension(path.to_str().unwrap(), &options, span.clone(), block);
-                                    return;
-                                }
-                                Err(_) => {}
-                            }
+                            Self::validate_extension(trimmed, &options, span.clone(), ctx);
+                            return;
                         }
 
                         ReportsBag::add(report!(
@@ -112,8 +112,14 @@ impl GenericValidator {
         })
     }
 
+    /// Pages served over HTTPS report `http://` resources as mixed content instead.
+    #[must_use]
     pub fn string_prefer_https(self) -> Self {
-        self.check_value(|value, span, _| {
+        self.check_value(|value, span, ctx| {
+            if ctx.info.is_https() {
+                return;
+            }
+
             if let Some(s) = value.kind.as_string() {
                 let trimmed = s.trim();
 
@@ -134,3 +140,14 @@ impl GenericValidator {
         })
     }
 }
+
+/// Why a protocol is disallowed.
+fn protocol_note(scheme: &str) -> &'static str {
+    match scheme {
+        "javascript" | "vbscript" => {
+            "Script URLs run code when followed, are blocked by a Content Security Policy and are a common XSS vector. Attach an event listener from a script file instead"
+        }
+        "data" => "Data URLs can smuggle scripts and documents past content filters",
+        _ => "Use a http(s) URL or a path to a local file",
+    }
+}